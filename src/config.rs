@@ -0,0 +1,31 @@
+use std::fmt;
+
+pub mod detectors;
+pub mod watcher;
+
+/// An error found while loading a configuration, `path` locates the offending entry
+/// (`detectors[2] "hp_bar".colors[0]`) and is empty for errors about the whole file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError {
+    pub path: String,
+    pub message: String,
+}
+
+impl ConfigError {
+    pub fn new(path: &str, message: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
@@ -0,0 +1,382 @@
+use std::{fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    data::{normalized_zone::NormalizedZone, point::Point},
+    image_analyzer::{
+        color::{hsv::Hsv, rgb::Rgb, Color},
+        debounce::{Condition, Debounce},
+        detection::ColorDetection,
+        detector::{Detector, DetectorSet, PostProcess, ScanStrategy},
+        ImageZone,
+    },
+};
+
+use super::ConfigError;
+
+/// Root of a detector configuration file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DetectorsConfig {
+    pub detectors: Vec<DetectorConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DetectorConfig {
+    pub name: String,
+    /// Reference colors in any form `Color` parses, a pixel matching one of them is detected
+    pub colors: Vec<String>,
+    pub tolerance: ToleranceConfig,
+    #[serde(default)]
+    pub zones: Vec<ZoneConfig>,
+    #[serde(default)]
+    pub scan: ScanStrategy,
+    #[serde(default)]
+    pub post: PostProcess,
+    /// Reported state only changes once the condition holds over several frames
+    #[serde(default)]
+    pub debounce: Option<Debounce>,
+}
+
+/// Tolerance and the color space it applies in, `{"mode": "rgb", "r": 10, ...}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "lowercase", deny_unknown_fields)]
+pub enum ToleranceConfig {
+    Rgb {
+        r: u8,
+        g: u8,
+        b: u8,
+        #[serde(default)]
+        a: u8,
+    },
+    Hsv {
+        h: f64,
+        s: f64,
+        v: f64,
+        #[serde(default)]
+        a: f64,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ZoneKeyword {
+    Full,
+}
+
+/// `"full"`, `{"start": {"x": 0, "y": 0}, "end": {"x": 100, "y": 20}}` in pixels or a
+/// `NormalizedZone` under `normalized`, such as
+/// `{"normalized": {"start": {"x": 0.0, "y": {"from_end": 40}}, "end": {"x": 0.5, "y": 1.0}}}`.
+/// End excluded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ZoneConfig {
+    Keyword(ZoneKeyword),
+    Partial { start: Point, end: Point },
+    Normalized { normalized: NormalizedZone },
+}
+
+#[allow(dead_code)]
+impl DetectorsConfig {
+    pub fn from_json(text: &str) -> Result<Self, ConfigError> {
+        serde_json::from_str(text).map_err(|error| ConfigError::new("", &error.to_string()))
+    }
+
+    /// Reads, parses and validates a configuration file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<DetectorSet, Vec<ConfigError>> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|error| {
+            vec![ConfigError::new(
+                "",
+                &format!("cannot read {}: {}", path.display(), error),
+            )]
+        })?;
+        Self::from_json(&text).map_err(|error| vec![error])?.build()
+    }
+
+    /// Validates every entry and builds the detectors, reporting all the errors found
+    pub fn build(&self) -> Result<DetectorSet, Vec<ConfigError>> {
+        let mut errors = Vec::new();
+        let mut detectors = Vec::new();
+        for (index, config) in self.detectors.iter().enumerate() {
+            let path = format!("detectors[{}] \"{}\"", index, config.name);
+            if config.name.trim().is_empty() {
+                errors.push(ConfigError::new(&path, "name is empty"));
+            }
+            if let Some(first) = self.detectors[..index]
+                .iter()
+                .position(|other| other.name == config.name)
+            {
+                errors.push(ConfigError::new(
+                    &format!("{}.name", path),
+                    &format!("duplicate name, first used by detectors[{}]", first),
+                ));
+            }
+            match config.detector(&path) {
+                Ok(detector) => detectors.push(detector),
+                Err(mut detector_errors) => errors.append(&mut detector_errors),
+            }
+        }
+        if errors.is_empty() {
+            Ok(DetectorSet::new(detectors))
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl DetectorConfig {
+    fn detector(&self, path: &str) -> Result<Detector, Vec<ConfigError>> {
+        let mut errors = Vec::new();
+        if self.colors.is_empty() {
+            errors.push(ConfigError::new(&format!("{}.colors", path), "no color"));
+        }
+        let colors: Vec<Color> = self
+            .colors
+            .iter()
+            .enumerate()
+            .filter_map(|(index, text)| match text.parse::<Color>() {
+                Ok(color) => Some(color),
+                Err(error) => {
+                    errors.push(ConfigError::new(
+                        &format!("{}.colors[{}]", path, index),
+                        &error,
+                    ));
+                    None
+                }
+            })
+            .collect();
+        if let ToleranceConfig::Hsv { h, s, v, a } = self.tolerance {
+            let tolerance = Hsv { h, s, v, a };
+            let valid = Hsv::valid_bounds(&tolerance).and_then(|_| {
+                if (0.0..=1.0).contains(&a) {
+                    Ok(())
+                } else {
+                    Err("Alpha is not between 0.0 and 1.0".to_string())
+                }
+            });
+            if let Err(error) = valid {
+                errors.push(ConfigError::new(&format!("{}.tolerance", path), &error));
+            }
+        }
+        let mut zones = Vec::new();
+        let mut normalized_zones = Vec::new();
+        for (index, zone) in self.zones.iter().enumerate() {
+            match zone {
+                ZoneConfig::Keyword(ZoneKeyword::Full) => zones.push(ImageZone::Full),
+                ZoneConfig::Partial { start, end } if start.x < end.x && start.y < end.y => {
+                    zones.push(ImageZone::Partial(start.clone(), end.clone()))
+                }
+                // Offsets depend on the frame size, only fractions are checked here
+                ZoneConfig::Normalized { normalized } if normalized.fractions_ordered() => {
+                    normalized_zones.push(*normalized)
+                }
+                _ => errors.push(ConfigError::new(
+                    &format!("{}.zones[{}]", path, index),
+                    "start must be above and left of end",
+                )),
+            }
+        }
+        if let ScanStrategy::Pyramid { depth } = self.scan {
+            if !(1..=8).contains(&depth) {
+                errors.push(ConfigError::new(
+                    &format!("{}.scan", path),
+                    "pyramid depth is not between 1 and 8",
+                ));
+            }
+        }
+        if self.scan == ScanStrategy::Simd && matches!(self.tolerance, ToleranceConfig::Hsv { .. })
+        {
+            errors.push(ConfigError::new(
+                &format!("{}.scan", path),
+                "simd scan needs the rgb tolerance mode",
+            ));
+        }
+        if let Some(blobs) = &self.post.blobs {
+            if blobs.min_size == 0 {
+                errors.push(ConfigError::new(
+                    &format!("{}.post.blobs.min_size", path),
+                    "must be at least 1",
+                ));
+            }
+        }
+        let debounce = self.debounce.as_ref().map_or(Ok(()), |debounce| {
+            debounce.validate()?;
+            match debounce.condition {
+                // Blobs are only extracted with post.blobs, the count would stay at 0
+                Condition::MinBlobs(_) if self.post.blobs.is_none() => {
+                    Err("min_blobs needs post.blobs".to_string())
+                }
+                _ => Ok(()),
+            }
+        });
+        if let Err(error) = debounce {
+            errors.push(ConfigError::new(&format!("{}.debounce", path), &error));
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let detections: Vec<ColorDetection> = colors
+            .iter()
+            .map(|color| match self.tolerance {
+                ToleranceConfig::Rgb { r, g, b, a } => {
+                    ColorDetection::rgb(color.get_rgb(), Rgb { r, g, b, a })
+                }
+                ToleranceConfig::Hsv { h, s, v, a } => {
+                    ColorDetection::hsv(color.get_hsv(), Hsv { h, s, v, a })
+                }
+            })
+            .collect();
+        let detection = match detections.len() {
+            1 => detections.into_iter().next().unwrap(),
+            _ => ColorDetection::Any(detections),
+        };
+        Detector::new(
+            &self.name,
+            detection,
+            zones,
+            self.scan.clone(),
+            self.post.clone(),
+        )
+        .map(|detector| {
+            detector
+                .with_normalized_zones(normalized_zones)
+                .with_debounce(self.debounce.clone())
+        })
+        .map_err(|error| vec![ConfigError::new(path, &error)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image_analyzer::debounce::Condition;
+
+    fn build(json: &str) -> Result<DetectorSet, Vec<String>> {
+        DetectorsConfig::from_json(json)
+            .map_err(|error| vec![error.to_string()])?
+            .build()
+            .map_err(|errors| errors.iter().map(|error| error.to_string()).collect())
+    }
+
+    #[test]
+    fn test_load_example() {
+        let set = DetectorsConfig::load("detectors.example.json").unwrap();
+        assert_eq!(set.len(), 3);
+        let hp = set.get("hp_bar").unwrap();
+        assert_eq!(hp.scan, ScanStrategy::Simd);
+        assert_eq!(hp.zones.len(), 1);
+        assert!(matches!(hp.detection, ColorDetection::Any(ref colors) if colors.len() == 2));
+        let monsters = set.get("monsters").unwrap();
+        assert_eq!(monsters.scan, ScanStrategy::Pyramid { depth: 2 });
+        assert!(monsters.post.merge_zones);
+        assert_eq!(
+            monsters
+                .debounce
+                .as_ref()
+                .map(|debounce| debounce.condition.clone()),
+            Some(Condition::MinBlobs(1))
+        );
+        assert!(hp.debounce.is_none());
+    }
+
+    #[test]
+    fn test_minimal_detector() {
+        let set = build(
+            r##"{"detectors": [{"name": "red", "colors": ["#ff0000"],
+                "tolerance": {"mode": "rgb", "r": 5, "g": 5, "b": 5}}]}"##,
+        )
+        .unwrap();
+        let red = set.get("red").unwrap();
+        assert_eq!(red.scan, ScanStrategy::Full);
+        assert!(red.zones.is_empty());
+        assert_eq!(
+            red.detection,
+            ColorDetection::rgb(Rgb::from([255, 0, 0]), Rgb::from([5, 5, 5, 0]))
+        );
+    }
+
+    #[test]
+    fn test_normalized_zones() {
+        let set = build(
+            r##"{"detectors": [{"name": "hud", "colors": ["#ff0000"],
+                "tolerance": {"mode": "rgb", "r": 0, "g": 0, "b": 0},
+                "zones": [{"start": {"x": 0, "y": 0}, "end": {"x": 4, "y": 4}},
+                          {"normalized": {"start": {"x": 0.5, "y": {"from_end": 2}},
+                                          "end": {"x": 1.0, "y": 1.0}}}]}]}"##,
+        )
+        .unwrap();
+        let hud = set.get("hud").unwrap();
+        assert_eq!(hud.zones.len(), 1);
+        assert_eq!(hud.normalized_zones.len(), 1);
+
+        // Red everywhere, the normalized zone grows with the frame
+        for (width, height) in [(8, 8), (40, 20)] {
+            let image =
+                image::ImageBuffer::from_pixel(width, height, image::Rgba([255, 0, 0, 255]));
+            let result = hud.run(&crate::image_analyzer::ImageAnalyzer::new(image));
+            assert_eq!(result.pixels.points_count(), 16 + width as usize / 2 * 2);
+        }
+
+        // Fractions are never read as pixels, nor pixels as fractions, without the tag
+        let errors = build(
+            r##"{"detectors": [{"name": "hud", "colors": ["#ff0000"],
+                "tolerance": {"mode": "rgb", "r": 0, "g": 0, "b": 0},
+                "zones": [{"start": {"x": 0.5, "y": 0.0}, "end": {"x": 1.0, "y": 1.0}}]}]}"##,
+        )
+        .err()
+        .unwrap();
+        assert!(errors[0].contains("line 3"), "{}", errors[0]);
+    }
+
+    #[test]
+    fn test_errors_point_to_entry() {
+        let errors = build(
+            r##"{"detectors": [
+                {"name": "ok", "colors": ["red"], "tolerance": {"mode": "rgb", "r": 1, "g": 1, "b": 1}},
+                {"name": "bad", "colors": ["red", "#12"],
+                 "tolerance": {"mode": "hsv", "h": 400, "s": 0.1, "v": 0.1},
+                 "zones": ["full", {"start": {"x": 10, "y": 10}, "end": {"x": 5, "y": 20}},
+                           {"normalized": {"start": {"x": 0.2, "y": 0.5}, "end": {"x": 0.8, "y": 0.5}}},
+                           {"normalized": {"start": {"x": 0.5, "y": 0.5}, "end": {"x": {"from_end": 10}, "y": 1.0}}}],
+                 "scan": "simd",
+                 "post": {"blobs": {"min_size": 0}},
+                 "debounce": {"condition": {"min_pixels": 10}, "window": 3, "on": 4, "off": 1}},
+                {"name": "ok", "colors": [], "tolerance": {"mode": "rgb", "r": 1, "g": 1, "b": 1},
+                 "scan": {"pyramid": {"depth": 12}},
+                 "debounce": {"condition": {"min_blobs": 1}, "window": 3, "on": 2, "off": 0}}
+            ]}"##,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            errors,
+            vec![
+                "detectors[1] \"bad\".colors[1]: Invalid color \"#12\": expected 3, 4, 6 or 8 hexadecimal digits",
+                "detectors[1] \"bad\".tolerance: Hue is not between 0.0 and 360.0",
+                "detectors[1] \"bad\".zones[1]: start must be above and left of end",
+                "detectors[1] \"bad\".zones[2]: start must be above and left of end",
+                "detectors[1] \"bad\".scan: simd scan needs the rgb tolerance mode",
+                "detectors[1] \"bad\".post.blobs.min_size: must be at least 1",
+                "detectors[1] \"bad\".debounce: on must be between 1 and window",
+                "detectors[2] \"ok\".name: duplicate name, first used by detectors[0]",
+                "detectors[2] \"ok\".colors: no color",
+                "detectors[2] \"ok\".scan: pyramid depth is not between 1 and 8",
+                "detectors[2] \"ok\".debounce: min_blobs needs post.blobs",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_schema_errors() {
+        let errors =
+            build(r#"{"detectors": [{"name": "x", "colors": [], "tolerance": {"mode": "lab"}}]}"#)
+                .err()
+                .unwrap();
+        assert!(errors[0].contains("line 1"), "{}", errors[0]);
+        assert!(DetectorsConfig::load("missing.json").is_err());
+    }
+}
@@ -0,0 +1,212 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use crate::image_analyzer::detector::SharedDetectors;
+
+use super::{detectors::DetectorsConfig, ConfigError};
+
+#[derive(Debug, PartialEq)]
+pub enum ReloadStatus {
+    Unchanged,
+    Reloaded,
+    /// The new file was rejected, the previous detectors stay in use
+    Failed(Vec<ConfigError>),
+}
+
+/// Polls a detector configuration file and swaps the shared detector set when it
+/// changes and validates
+pub struct ConfigWatcher {
+    path: PathBuf,
+    /// File state of the detectors in use
+    loaded: FileStamp,
+    /// File state last rejected, not reported again until the file changes
+    failed: Option<FileStamp>,
+    detectors: SharedDetectors,
+}
+
+/// Modification time and length of a file. The length tells a half-written file from
+/// the finished one when both get the same time on a filesystem with coarse times.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FileStamp {
+    modified: Option<SystemTime>,
+    len: Option<u64>,
+}
+
+/// Stops the background polling thread when dropped
+pub struct WatchHandle {
+    stop: Arc<AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl FileStamp {
+    fn of(path: &Path) -> Self {
+        let metadata = fs::metadata(path).ok();
+        Self {
+            modified: metadata.as_ref().and_then(|meta| meta.modified().ok()),
+            len: metadata.map(|meta| meta.len()),
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl ConfigWatcher {
+    /// Loads the file once, failing like `DetectorsConfig::load` when it is invalid
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, Vec<ConfigError>> {
+        let path = path.as_ref().to_path_buf();
+        let loaded = FileStamp::of(&path);
+        let set = DetectorsConfig::load(&path)?;
+        Ok(Self {
+            path,
+            loaded,
+            failed: None,
+            detectors: SharedDetectors::new(set),
+        })
+    }
+
+    /// Handle to give to `ImageAnalyzer::with_detectors`
+    pub fn detectors(&self) -> SharedDetectors {
+        self.detectors.clone()
+    }
+
+    /// Reloads the file if its modification time or length changed since it was last
+    /// loaded or rejected
+    pub fn poll(&mut self) -> ReloadStatus {
+        let stamp = FileStamp::of(&self.path);
+        if stamp == self.loaded || Some(stamp) == self.failed {
+            return ReloadStatus::Unchanged;
+        }
+        match DetectorsConfig::load(&self.path) {
+            Ok(set) => {
+                self.detectors.replace(set);
+                self.loaded = stamp;
+                self.failed = None;
+                ReloadStatus::Reloaded
+            }
+            Err(errors) => {
+                self.failed = Some(stamp);
+                ReloadStatus::Failed(errors)
+            }
+        }
+    }
+
+    /// Polls every `interval` on a background thread, reporting each reload or failure
+    pub fn spawn<F>(mut self, interval: Duration, mut on_status: F) -> WatchHandle
+    where
+        F: FnMut(ReloadStatus) + Send + 'static,
+    {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let thread = thread::spawn(move || {
+            while !thread_stop.load(Ordering::Relaxed) {
+                let status = self.poll();
+                if status != ReloadStatus::Unchanged {
+                    on_status(status);
+                }
+                thread::sleep(interval);
+            }
+        });
+        WatchHandle {
+            stop,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for WatchHandle {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::File, io::Write};
+
+    use image::{ImageBuffer, Rgba};
+
+    use super::*;
+    use crate::image_analyzer::ImageAnalyzer;
+
+    fn config(color: &str) -> String {
+        format!(
+            r#"{{"detectors": [{{"name": "target", "colors": ["{}"],
+                "tolerance": {{"mode": "rgb", "r": 0, "g": 0, "b": 0}}}}]}}"#,
+            color
+        )
+    }
+
+    fn write(path: &Path, text: &str, modified: u64) {
+        let mut file = File::create(path).unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+        // Explicit times, the file is rewritten faster than some filesystems tick
+        file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(modified))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_reload_and_keep_on_failure() {
+        let path = std::env::temp_dir().join(format!("watcher_{}.json", std::process::id()));
+        write(&path, &config("red"), 1);
+        let mut watcher = ConfigWatcher::new(&path).unwrap();
+        let analyzer = ImageAnalyzer::new(ImageBuffer::from_fn(4, 4, |x, _| {
+            if x == 0 {
+                Rgba([255, 0, 0, 255])
+            } else {
+                Rgba([0, 0, 255, 255])
+            }
+        }))
+        .with_detectors(watcher.detectors());
+        let count = |analyzer: &ImageAnalyzer| analyzer.run_detectors()[0].pixels.points_count();
+        assert_eq!(count(&analyzer), 4);
+        assert_eq!(watcher.poll(), ReloadStatus::Unchanged);
+
+        // A frame holding a snapshot keeps its detectors across a swap
+        let in_progress = watcher.detectors().snapshot();
+        write(&path, &config("blue"), 2);
+        assert_eq!(watcher.poll(), ReloadStatus::Reloaded);
+        assert_eq!(count(&analyzer), 12);
+        assert_eq!(in_progress.run(&analyzer)[0].pixels.points_count(), 4);
+
+        write(&path, &config("not a color"), 3);
+        match watcher.poll() {
+            ReloadStatus::Failed(errors) => {
+                assert_eq!(errors[0].path, "detectors[0] \"target\".colors[0]")
+            }
+            status => panic!("{:?}", status),
+        }
+        assert_eq!(count(&analyzer), 12);
+        assert_eq!(watcher.poll(), ReloadStatus::Unchanged);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_reload_after_partial_write() {
+        let path =
+            std::env::temp_dir().join(format!("watcher_partial_{}.json", std::process::id()));
+        write(&path, &config("red"), 1);
+        let mut watcher = ConfigWatcher::new(&path).unwrap();
+
+        // Caught half-written, then finished within the same mtime tick
+        let blue = config("blue");
+        write(&path, &blue[..blue.len() / 2], 2);
+        assert!(matches!(watcher.poll(), ReloadStatus::Failed(_)));
+        assert_eq!(watcher.poll(), ReloadStatus::Unchanged);
+        write(&path, &blue, 2);
+        assert_eq!(watcher.poll(), ReloadStatus::Reloaded);
+        assert_eq!(watcher.poll(), ReloadStatus::Unchanged);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,2 +1,3 @@
 pub mod better_call_zone;
+pub mod normalized_zone;
 pub mod point;
@@ -32,8 +32,8 @@ impl Zone {
     }
 
     pub fn includes(&self, zone: Zone) -> bool {
-        let (min_x, min_y, max_x, max_y) = zone.area(800, 600);
-        let (min_x2, min_y2, max_x2, max_y2) = self.area(800, 600);
+        let (min_x, min_y, max_x, max_y) = zone.area(u32::MAX, u32::MAX);
+        let (min_x2, min_y2, max_x2, max_y2) = self.area(u32::MAX, u32::MAX);
         min_x >= min_x2 && min_y >= min_y2 && max_x <= max_x2 && max_y <= max_y2
     }
 
@@ -41,9 +41,28 @@ impl Zone {
         point.is_inside(&self.start, &self.end)
     }
 
+    pub fn overlaps(&self, zone: &Zone) -> bool {
+        let (min_x, min_y, max_x, max_y) = zone.area(u32::MAX, u32::MAX);
+        let (min_x2, min_y2, max_x2, max_y2) = self.area(u32::MAX, u32::MAX);
+        min_x <= max_x2 && min_x2 <= max_x && min_y <= max_y2 && min_y2 <= max_y
+    }
+
+    /// Grows into the bounding zone of both when they overlap or touch, `None` otherwise
+    pub fn merge_overlapping(&mut self, zone: &Zone) -> Option<&Zone> {
+        if !self.overlaps(zone) {
+            return None;
+        }
+        let (min_x, min_y, max_x, max_y) = zone.area(u32::MAX, u32::MAX);
+        let (min_x2, min_y2, max_x2, max_y2) = self.area(u32::MAX, u32::MAX);
+        self.start = Point::new(min_x.min(min_x2), min_y.min(min_y2));
+        self.end = Point::new(max_x.max(max_x2), max_y.max(max_y2));
+        self.size = self.start.distance(&self.end);
+        Some(self)
+    }
+
     pub fn extend(&mut self, zone: &mut Zone) -> Option<&Zone> {
-        let (min_x, min_y, max_x, max_y) = zone.area(800, 600);
-        let (min_x2, min_y2, max_x2, max_y2) = self.area(800, 600);
+        let (min_x, min_y, max_x, max_y) = zone.area(u32::MAX, u32::MAX);
+        let (min_x2, min_y2, max_x2, max_y2) = self.area(u32::MAX, u32::MAX);
         if self.start == zone.start {
             if self.end.x < zone.end.x {
                 self.end.x = zone.end.x;
@@ -101,6 +120,10 @@ impl ZoneManager {
         }
     }
 
+    pub fn zones(&self) -> &Vec<Zone> {
+        &self.zones
+    }
+
     pub fn add_zone(&mut self, zone: Zone) {
         let exist_zone = self.zones.iter().find(|z| *z == &zone);
         if exist_zone.is_none() {
@@ -108,6 +131,27 @@ impl ZoneManager {
         }
     }
 
+    /// Merges overlapping or touching zones with `Zone::merge_overlapping`, repeating
+    /// until stable as a merged zone can reach zones it did not touch before
+    pub fn merge_overlapping_zones(&mut self) {
+        loop {
+            let count = self.zones.len();
+            let zones = std::mem::take(&mut self.zones);
+            for zone in zones {
+                let merged = self
+                    .zones
+                    .iter_mut()
+                    .any(|z| z.merge_overlapping(&zone).is_some());
+                if !merged {
+                    self.zones.push(zone);
+                }
+            }
+            if self.zones.len() == count {
+                break;
+            }
+        }
+    }
+
     pub fn extend_zones(&mut self) {
         let zones = self.zones.clone();
         self.zones.clear();
@@ -155,6 +199,22 @@ mod tests {
         let zone3 = Zone::new(Point::new(0, 0), Point::new(10, 10));
         let zone4 = Zone::new(Point::new(0, 0), Point::new(5, 5));
         assert_eq!(zone3.includes(zone4), true);
+
+        // Zones beyond 800x600 are compared as they are
+        let zone5 = Zone::new(Point::new(0, 0), Point::new(900, 700));
+        let zone6 = Zone::new(Point::new(850, 650), Point::new(1000, 800));
+        assert!(!zone5.includes(zone6));
+    }
+
+    #[test]
+    fn test_zone_extend_beyond_800x600() {
+        let mut zone = Zone::new(Point::new(0, 0), Point::new(900, 700));
+        let mut zone2 = Zone::new(Point::new(850, 650), Point::new(1000, 800));
+        assert!(zone.extend(&mut zone2).is_none());
+        assert_eq!(zone.end, Point::new(900, 700));
+
+        let mut zone3 = Zone::new(Point::new(820, 620), Point::new(880, 680));
+        assert!(zone.extend(&mut zone3).is_some());
     }
 
     #[test]
@@ -173,6 +233,29 @@ mod tests {
         assert_eq!(zone.extend(&mut zone3).is_some(), true);
     }
 
+    #[test]
+    fn test_zone_merge_overlapping() {
+        let mut zone = Zone::new(Point::new(0, 0), Point::new(10, 10));
+        let zone2 = Zone::new(Point::new(10, 10), Point::new(20, 15));
+        assert!(zone.merge_overlapping(&zone2).is_some());
+        assert_eq!(zone.start, Point::new(0, 0));
+        assert_eq!(zone.end, Point::new(20, 15));
+
+        let zone3 = Zone::new(Point::new(30, 30), Point::new(40, 40));
+        assert!(zone.merge_overlapping(&zone3).is_none());
+
+        // The last zone bridges the first two
+        let mut zone_manager = ZoneManager::new(800, 600);
+        zone_manager.add_zone(Zone::new(Point::new(0, 0), Point::new(5, 5)));
+        zone_manager.add_zone(Zone::new(Point::new(20, 0), Point::new(25, 5)));
+        zone_manager.add_zone(Zone::new(Point::new(5, 0), Point::new(20, 2)));
+        zone_manager.merge_overlapping_zones();
+        assert_eq!(
+            zone_manager.zones(),
+            &vec![Zone::new(Point::new(0, 0), Point::new(25, 5))]
+        );
+    }
+
     #[test]
     fn test_zone_manager() {
         let mut zone_manager = ZoneManager::new(800, 600);
@@ -0,0 +1,158 @@
+use serde::{Deserialize, Serialize};
+
+use super::{better_call_zone::Zone, point::Point};
+
+/// Pixels counted from a reference line of the image, `{"from_end": 40}`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Offset {
+    /// From the left or top edge
+    #[serde(rename = "from_start")]
+    Start(i64),
+    /// Back from the right or bottom edge
+    #[serde(rename = "from_end")]
+    End(i64),
+    /// From the middle, negative towards the left or top
+    #[serde(rename = "from_center")]
+    Center(i64),
+}
+
+/// A coordinate along one axis: a bare number is a fraction of the dimension, `0.0`
+/// at the left or top edge and `1.0` at the right or bottom one
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Coordinate {
+    Fraction(f64),
+    Offset(Offset),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NormalizedPoint {
+    pub x: Coordinate,
+    pub y: Coordinate,
+}
+
+/// A zone that scales with the image, resolved against the actual dimensions of a
+/// frame. End excluded, like `Zone`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NormalizedZone {
+    pub start: NormalizedPoint,
+    pub end: NormalizedPoint,
+}
+
+#[allow(dead_code)]
+impl Coordinate {
+    /// Pixel position along a dimension of `length` pixels, clamped to `0..=length`
+    pub fn resolve(&self, length: u32) -> u32 {
+        let position = match *self {
+            Coordinate::Fraction(fraction) => (fraction * length as f64).round() as i64,
+            Coordinate::Offset(Offset::Start(pixels)) => pixels,
+            Coordinate::Offset(Offset::End(pixels)) => length as i64 - pixels,
+            Coordinate::Offset(Offset::Center(pixels)) => length as i64 / 2 + pixels,
+        };
+        position.clamp(0, length as i64) as u32
+    }
+}
+
+#[allow(dead_code)]
+impl NormalizedPoint {
+    pub fn fraction(x: f64, y: f64) -> Self {
+        Self {
+            x: Coordinate::Fraction(x),
+            y: Coordinate::Fraction(y),
+        }
+    }
+
+    pub fn resolve(&self, width: u32, height: u32) -> Point {
+        Point::new(self.x.resolve(width), self.y.resolve(height))
+    }
+}
+
+#[allow(dead_code)]
+impl NormalizedZone {
+    pub fn new(start: NormalizedPoint, end: NormalizedPoint) -> Self {
+        Self { start, end }
+    }
+
+    /// Fractions of a `width` x `height` image covering `zone`
+    pub fn from_zone(zone: &Zone, width: u32, height: u32) -> Self {
+        let (min_x, min_y, max_x, max_y) = zone.area(u32::MAX, u32::MAX);
+        let (width, height) = (width.max(1) as f64, height.max(1) as f64);
+        Self::new(
+            NormalizedPoint::fraction(min_x as f64 / width, min_y as f64 / height),
+            NormalizedPoint::fraction(max_x as f64 / width, max_y as f64 / height),
+        )
+    }
+
+    /// False when the fractions of an axis place start at or past end, which leaves the
+    /// zone empty at any resolution. Axes with an offset are not checked.
+    pub fn fractions_ordered(&self) -> bool {
+        let ordered = |start: Coordinate, end: Coordinate| match (start, end) {
+            (Coordinate::Fraction(start), Coordinate::Fraction(end)) => start < end,
+            _ => true,
+        };
+        ordered(self.start.x, self.end.x) && ordered(self.start.y, self.end.y)
+    }
+
+    /// Absolute zone inside a `width` x `height` image, `None` when it is empty there
+    pub fn resolve(&self, width: u32, height: u32) -> Option<Zone> {
+        let start = self.start.resolve(width, height);
+        let end = self.end.resolve(width, height);
+        (start.x < end.x && start.y < end.y).then(|| Zone::new(start, end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_at_any_resolution() {
+        // Right half of the bottom 40 pixels, and a centered 200x100 box
+        let bar: NormalizedZone = serde_json::from_str(
+            r#"{"start": {"x": 0.5, "y": {"from_end": 40}}, "end": {"x": 1.0, "y": 1.0}}"#,
+        )
+        .unwrap();
+        let center = NormalizedZone::new(
+            NormalizedPoint {
+                x: Coordinate::Offset(Offset::Center(-100)),
+                y: Coordinate::Offset(Offset::Center(-50)),
+            },
+            NormalizedPoint {
+                x: Coordinate::Offset(Offset::Center(100)),
+                y: Coordinate::Offset(Offset::Center(50)),
+            },
+        );
+        for (width, height) in [(800, 600), (1920, 1080)] {
+            let zone = bar.resolve(width, height).unwrap();
+            assert_eq!(zone.start, Point::new(width / 2, height - 40));
+            assert_eq!(zone.end, Point::new(width, height));
+            let zone = center.resolve(width, height).unwrap();
+            assert_eq!(zone.start, Point::new(width / 2 - 100, height / 2 - 50));
+            assert_eq!(zone.end, Point::new(width / 2 + 100, height / 2 + 50));
+        }
+        // Clamped to the image, then empty
+        assert_eq!(center.resolve(100, 50).unwrap().end, Point::new(100, 50));
+        let outside = NormalizedZone::new(
+            NormalizedPoint::fraction(1.2, 0.0),
+            NormalizedPoint::fraction(1.5, 1.0),
+        );
+        assert_eq!(outside.resolve(800, 600), None);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let zone = Zone::new(Point::new(200, 150), Point::new(400, 600));
+        let normalized = NormalizedZone::from_zone(&zone, 800, 600);
+        assert_eq!(normalized.start, NormalizedPoint::fraction(0.25, 0.25));
+        let json = serde_json::to_string(&normalized).unwrap();
+        assert_eq!(
+            json,
+            r#"{"start":{"x":0.25,"y":0.25},"end":{"x":0.5,"y":1.0}}"#
+        );
+        let parsed: NormalizedZone = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            parsed.resolve(1600, 1200).unwrap().end,
+            Point::new(800, 1200)
+        );
+    }
+}
@@ -1,15 +1,39 @@
 use image::ImageBuffer;
 use rayon::iter::{ParallelBridge, ParallelIterator};
 
-use crate::data::point::Point;
+use crate::data::{better_call_zone::Zone, point::Point};
 
 use self::{
     color::{rgb::Rgb, Color},
+    detection::ColorDetection,
+    detector::SharedDetectors,
     pixel::PixelVec,
 };
 
+pub mod blob;
+pub mod cache;
 pub mod color;
+pub mod debounce;
+pub mod debug_image;
+pub mod detection;
+pub mod detector;
+pub mod frame_diff;
+pub mod frame_source;
+pub mod gauge;
+pub mod glyph;
+pub mod kalman;
+pub mod layout;
+pub mod lut;
+pub mod mask;
+pub mod minimap;
+pub mod phash;
 pub mod pixel;
+pub mod pyramid;
+pub mod recording;
+pub mod screen_state;
+pub mod simd;
+pub mod template;
+pub mod tracker;
 #[allow(dead_code)]
 pub enum LoopResult {
     Continue(Axis),
@@ -21,18 +45,79 @@ pub enum Axis {
     Y,
 }
 #[allow(dead_code)]
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub enum ImageZone {
     Full,
     Partial(Point, Point),
 }
+#[allow(dead_code)]
+impl ImageZone {
+    /// Returns `(start_x, start_y, end_x, end_y)` of the zone, end excluded
+    pub fn bounds(&self, width: u32, height: u32) -> (u32, u32, u32, u32) {
+        match self {
+            ImageZone::Full => (0, 0, width, height),
+            ImageZone::Partial(start, end) => {
+                if start.x > end.x || start.y > end.y || end.x > width || end.y > height {
+                    panic!("Invalid zone");
+                }
+                (start.x, start.y, end.x, end.y)
+            }
+        }
+    }
+
+    /// Whether `point` is inside of the zone, end excluded
+    pub fn contains(&self, point: &Point) -> bool {
+        match self {
+            ImageZone::Full => true,
+            ImageZone::Partial(start, end) => {
+                (start.x..end.x).contains(&point.x) && (start.y..end.y).contains(&point.y)
+            }
+        }
+    }
+
+    /// The part of the zone inside a `width` x `height` image, `None` when empty
+    pub fn clip(&self, width: u32, height: u32) -> Option<ImageZone> {
+        match self {
+            ImageZone::Full => Some(ImageZone::Full),
+            ImageZone::Partial(start, end) => {
+                let end = Point::new(end.x.min(width), end.y.min(height));
+                (start.x < end.x && start.y < end.y).then(|| ImageZone::Partial(start.clone(), end))
+            }
+        }
+    }
+}
+impl From<&Zone> for ImageZone {
+    fn from(zone: &Zone) -> Self {
+        let (min_x, min_y, max_x, max_y) = zone.area(u32::MAX, u32::MAX);
+        ImageZone::Partial(Point::new(min_x, min_y), Point::new(max_x, max_y))
+    }
+}
 pub struct ImageAnalyzer {
     pub image: ImageBuffer<image::Rgba<u8>, Vec<u8>>,
+    pub previous: Option<ImageBuffer<image::Rgba<u8>, Vec<u8>>>,
+    /// Detectors run by `run_detectors`, possibly swapped by a config watcher
+    pub detectors: Option<SharedDetectors>,
 }
 
 impl ImageAnalyzer {
     pub fn new(image: ImageBuffer<image::Rgba<u8>, Vec<u8>>) -> Self {
-        Self { image }
+        Self {
+            image,
+            previous: None,
+            detectors: None,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn with_detectors(mut self, detectors: SharedDetectors) -> Self {
+        self.detectors = Some(detectors);
+        self
+    }
+
+    /// Replaces the analyzed frame, keeping the current one as the previous frame
+    #[allow(dead_code)]
+    pub fn next_frame(&mut self, image: ImageBuffer<image::Rgba<u8>, Vec<u8>>) {
+        self.previous = Some(std::mem::replace(&mut self.image, image));
     }
 
     pub fn par_pixel_detectv1(&self) -> PixelVec {
@@ -139,8 +224,8 @@ impl ImageAnalyzer {
             }
         };
 
-        'outer: for y in start_y..height {
-            for x in start_x..width {
+        'outer: for y in start_y..start_y + height {
+            for x in start_x..start_x + width {
                 let pixel = self.image.get_pixel(x, y);
                 let rgb = Rgb::from(pixel.0);
                 let loop_result = callback(Color::Rgb(rgb), Point { x, y });
@@ -164,6 +249,17 @@ impl ImageAnalyzer {
         }
     }
     #[allow(dead_code)]
+    pub fn detect(&self, zone: ImageZone, detection: &ColorDetection) -> PixelVec {
+        let mut points = PixelVec::new();
+        self.pixel_detectv2(zone, |color, point| {
+            if detection.matches(&color) {
+                points.push((color, point));
+            }
+            None
+        });
+        points
+    }
+    #[allow(dead_code)]
     pub fn batch_zones(&self, zones: Vec<ImageZone>) -> Vec<ImageZone> {
         // merge zones that have the exact same zone
         let mut zones_result = Vec::new();
@@ -185,3 +281,24 @@ impl ImageAnalyzer {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pixel_detectv2_partial_zone() {
+        let analyzer = ImageAnalyzer::new(ImageBuffer::new(30, 30));
+        let mut points = Vec::new();
+        analyzer.pixel_detectv2(
+            ImageZone::Partial(Point::new(10, 5), Point::new(20, 25)),
+            |_, point| {
+                points.push(point);
+                None
+            },
+        );
+        assert_eq!(points.len(), 10 * 20);
+        assert_eq!(points.first(), Some(&Point::new(10, 5)));
+        assert_eq!(points.last(), Some(&Point::new(19, 24)));
+    }
+}
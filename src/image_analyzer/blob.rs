@@ -0,0 +1,125 @@
+use crate::data::{better_call_zone::Zone, point::Point};
+
+use super::pixel::PixelVec;
+
+/// A group of 8-connected detected points
+#[derive(Debug, Clone, PartialEq)]
+pub struct Blob {
+    pub points: Vec<Point>,
+    /// Bounding zone of the points, end excluded
+    pub zone: Zone,
+    /// Mean position of the points, rounded
+    pub centroid: Point,
+}
+#[allow(dead_code)]
+impl Blob {
+    pub fn size(&self) -> usize {
+        self.points.len()
+    }
+
+    fn new(points: Vec<Point>) -> Self {
+        let mut min = points[0].clone();
+        let mut max = points[0].clone();
+        let (mut sum_x, mut sum_y) = (0u64, 0u64);
+        for point in &points {
+            min = Point::new(min.x.min(point.x), min.y.min(point.y));
+            max = Point::new(max.x.max(point.x), max.y.max(point.y));
+            sum_x += point.x as u64;
+            sum_y += point.y as u64;
+        }
+        let count = points.len() as f64;
+        let centroid = Point::new(
+            (sum_x as f64 / count).round() as u32,
+            (sum_y as f64 / count).round() as u32,
+        );
+        Self {
+            points,
+            zone: Zone::new(min, Point::new(max.x + 1, max.y + 1)),
+            centroid,
+        }
+    }
+}
+
+/// Groups points into 8-connected blobs of at least `min_size` points, ordered by
+/// their first point in row-major order
+pub fn extract_blobs(points: &[Point], min_size: usize) -> Vec<Blob> {
+    if points.is_empty() {
+        return Vec::new();
+    }
+    let min_x = points.iter().map(|p| p.x).min().unwrap();
+    let min_y = points.iter().map(|p| p.y).min().unwrap();
+    let width = (points.iter().map(|p| p.x).max().unwrap() - min_x + 1) as usize;
+    let height = (points.iter().map(|p| p.y).max().unwrap() - min_y + 1) as usize;
+    let mut grid = vec![false; width * height];
+    for point in points {
+        grid[(point.y - min_y) as usize * width + (point.x - min_x) as usize] = true;
+    }
+
+    let mut blobs = Vec::new();
+    let mut stack = Vec::new();
+    for start in 0..grid.len() {
+        if !grid[start] {
+            continue;
+        }
+        grid[start] = false;
+        stack.push(start);
+        let mut blob = Vec::new();
+        while let Some(index) = stack.pop() {
+            let (x, y) = (index % width, index / width);
+            blob.push(Point::new(x as u32 + min_x, y as u32 + min_y));
+            for ny in y.saturating_sub(1)..=(y + 1).min(height - 1) {
+                for nx in x.saturating_sub(1)..=(x + 1).min(width - 1) {
+                    let neighbour = ny * width + nx;
+                    if grid[neighbour] {
+                        grid[neighbour] = false;
+                        stack.push(neighbour);
+                    }
+                }
+            }
+        }
+        if blob.len() >= min_size {
+            blob.sort_by_key(|point| (point.y, point.x));
+            blobs.push(Blob::new(blob));
+        }
+    }
+    blobs
+}
+
+#[allow(dead_code)]
+impl PixelVec {
+    pub fn blobs(&self, min_size: usize) -> Vec<Blob> {
+        extract_blobs(&self.points(), min_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_blobs() {
+        let mut points = Vec::new();
+        for y in 10..14 {
+            for x in 20..25 {
+                points.push(Point::new(x, y));
+            }
+        }
+        // Diagonal neighbours belong to the same blob
+        points.push(Point::new(25, 14));
+        points.push(Point::new(2, 3));
+        points.push(Point::new(40, 1));
+        points.push(Point::new(41, 1));
+
+        let blobs = extract_blobs(&points, 1);
+        assert_eq!(blobs.len(), 3);
+        assert_eq!(blobs[0].points, vec![Point::new(40, 1), Point::new(41, 1)]);
+        assert_eq!(blobs[1].size(), 1);
+        assert_eq!(blobs[2].size(), 21);
+        assert_eq!(blobs[2].zone.start, Point::new(20, 10));
+        assert_eq!(blobs[2].zone.end, Point::new(26, 15));
+        assert_eq!(blobs[2].centroid, Point::new(22, 12));
+
+        assert_eq!(extract_blobs(&points, 2).len(), 2);
+        assert!(extract_blobs(&[], 1).is_empty());
+    }
+}
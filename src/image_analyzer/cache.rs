@@ -0,0 +1,174 @@
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use super::{detection::ColorDetection, pixel::PixelVec, ImageAnalyzer, ImageZone};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+#[allow(dead_code)]
+impl CacheStats {
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+struct CacheEntry {
+    hash: u64,
+    detection: ColorDetection,
+    result: PixelVec,
+}
+
+/// Detection results kept across frames, keyed by zone and detector name.
+///
+/// A result is reused as long as the pixels of its zone hash to the same value and the
+/// detector still uses the same `ColorDetection`.
+pub struct DetectionCache {
+    entries: HashMap<(ImageZone, String), CacheEntry>,
+    stats: CacheStats,
+}
+#[allow(dead_code)]
+impl DetectionCache {
+    pub fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+            stats: CacheStats::default(),
+        }
+    }
+
+    pub fn detect(
+        &mut self,
+        analyzer: &ImageAnalyzer,
+        zone: ImageZone,
+        name: &str,
+        detection: &ColorDetection,
+    ) -> &PixelVec {
+        let hash = analyzer.zone_hash(&zone);
+        let key = (zone, name.to_string());
+        let fresh = self
+            .entries
+            .get(&key)
+            .is_some_and(|entry| entry.hash == hash && entry.detection == *detection);
+        if fresh {
+            self.stats.hits += 1;
+        } else {
+            self.stats.misses += 1;
+            let result = analyzer.detect(key.0.clone(), detection);
+            self.entries.insert(
+                key.clone(),
+                CacheEntry {
+                    hash,
+                    detection: detection.clone(),
+                    result,
+                },
+            );
+        }
+        &self.entries[&key].result
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = CacheStats::default();
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[allow(dead_code)]
+impl ImageAnalyzer {
+    /// Hash of the raw pixels of a zone, used to tell whether it changed between frames
+    pub fn zone_hash(&self, zone: &ImageZone) -> u64 {
+        let (start_x, start_y, end_x, end_y) = zone.bounds(self.image.width(), self.image.height());
+        let raw: &[u8] = &self.image;
+        let stride = self.image.width() as usize * 4;
+        let mut hasher = DefaultHasher::new();
+        (end_x - start_x, end_y - start_y).hash(&mut hasher);
+        for y in start_y..end_y {
+            let row = y as usize * stride;
+            hasher.write(&raw[row + start_x as usize * 4..row + end_x as usize * 4]);
+        }
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{ImageBuffer, Rgba};
+
+    use crate::{data::point::Point, image_analyzer::color::rgb::Rgb};
+
+    use super::*;
+
+    fn count(
+        cache: &mut DetectionCache,
+        analyzer: &ImageAnalyzer,
+        zone: &ImageZone,
+        detection: &ColorDetection,
+    ) -> usize {
+        cache
+            .detect(analyzer, zone.clone(), "red", detection)
+            .points_count()
+    }
+
+    #[test]
+    fn test_cache_hits_and_misses() {
+        let mut analyzer =
+            ImageAnalyzer::new(ImageBuffer::from_pixel(64, 64, Rgba([255, 0, 0, 255])));
+        let mut cache = DetectionCache::new();
+        let left = ImageZone::Partial(Point::new(0, 0), Point::new(32, 64));
+        let right = ImageZone::Partial(Point::new(32, 0), Point::new(64, 64));
+        let red = ColorDetection::rgb(Rgb::from([255, 0, 0]), Rgb::from([0, 0, 0, 0]));
+
+        assert_eq!(count(&mut cache, &analyzer, &left, &red), 2048);
+        assert_eq!(count(&mut cache, &analyzer, &right, &red), 2048);
+        assert_eq!(count(&mut cache, &analyzer, &left, &red), 2048);
+        assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 2 });
+
+        // Only the right half changes on the next frame
+        let mut next = analyzer.image.clone();
+        next.put_pixel(40, 10, Rgba([0, 0, 255, 255]));
+        analyzer.next_frame(next);
+        assert_eq!(count(&mut cache, &analyzer, &left, &red), 2048);
+        assert_eq!(count(&mut cache, &analyzer, &right, &red), 2047);
+        assert_eq!(cache.stats(), CacheStats { hits: 2, misses: 3 });
+
+        // Changing the detection under the same name invalidates the entry
+        let dark_red = ColorDetection::rgb(Rgb::from([200, 0, 0]), Rgb::from([0, 0, 0, 0]));
+        assert_eq!(count(&mut cache, &analyzer, &left, &dark_red), 0);
+        assert_eq!(cache.stats().misses, 4);
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn test_zone_hash() {
+        let mut image = ImageBuffer::from_pixel(16, 16, Rgba([0, 0, 0, 255]));
+        let zone = ImageZone::Partial(Point::new(0, 0), Point::new(8, 8));
+        let before = ImageAnalyzer::new(image.clone()).zone_hash(&zone);
+        image.put_pixel(12, 12, Rgba([1, 0, 0, 255]));
+        assert_eq!(ImageAnalyzer::new(image.clone()).zone_hash(&zone), before);
+        image.put_pixel(7, 7, Rgba([1, 0, 0, 255]));
+        assert_ne!(ImageAnalyzer::new(image).zone_hash(&zone), before);
+    }
+}
@@ -1,11 +1,15 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
 pub mod color_from;
 pub mod conversion;
 pub mod hsv;
+pub mod named;
+pub mod parse;
 pub mod rgb;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// Serializes in the struct form, deserializes from the struct form or from a color
+/// string such as `"#ff8000"`, `"hsv(30, 1, 1)"` or `"orange"`.
+#[derive(Debug, Clone, Serialize, PartialEq)]
 pub enum Color {
     Rgb(rgb::Rgb),
     Hsv(hsv::Hsv),
@@ -27,3 +31,94 @@ impl Color {
         }
     }
 }
+
+#[derive(Deserialize)]
+enum ColorStruct {
+    Rgb(rgb::Rgb),
+    Hsv(hsv::Hsv),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ColorRepr {
+    Text(String),
+    Struct(ColorStruct),
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match ColorRepr::deserialize(deserializer)? {
+            ColorRepr::Text(text) => text.parse().map_err(serde::de::Error::custom),
+            ColorRepr::Struct(ColorStruct::Rgb(rgb)) => Ok(Color::Rgb(rgb)),
+            ColorRepr::Struct(ColorStruct::Hsv(hsv)) => Ok(Color::Hsv(hsv)),
+        }
+    }
+}
+
+/// `#[serde(with = "string_form")]` to serialize a `Color` as its string form
+#[allow(dead_code)]
+pub mod string_form {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::Color;
+
+    pub fn serialize<S>(color: &Color, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(color)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Color, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Color::deserialize(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Config {
+        #[serde(with = "string_form")]
+        text: Color,
+        plain: Color,
+    }
+
+    #[test]
+    fn test_deserialize_both_forms() {
+        let color: Color = serde_json::from_str(r##""#ff0000""##).unwrap();
+        assert_eq!(color, Color::Rgb(rgb::Rgb::from([255, 0, 0])));
+        let color: Color =
+            serde_json::from_str(r#"{"Rgb": {"r": 255, "g": 0, "b": 0, "a": 255}}"#).unwrap();
+        assert_eq!(color, Color::Rgb(rgb::Rgb::from([255, 0, 0])));
+        let error = serde_json::from_str::<Color>(r#""bluish""#).unwrap_err();
+        assert!(
+            error.to_string().contains("unknown color name"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_string_form() {
+        let config = Config {
+            text: Color::Hsv(hsv::Hsv::from([90.0, 0.5, 0.25])),
+            plain: Color::Rgb(rgb::Rgb::from([1, 2, 3])),
+        };
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(
+            json,
+            r#"{"text":"hsv(90, 0.5, 0.25)","plain":{"Rgb":{"r":1,"g":2,"b":3,"a":255}}}"#
+        );
+        assert_eq!(serde_json::from_str::<Config>(&json).unwrap(), config);
+    }
+}
@@ -1,6 +1,34 @@
 use super::hsv::Hsv;
 use super::rgb::Rgb;
 
+/// Hue in degrees brought into `[0.0, 360.0)`, NaN becomes `0.0`
+pub fn normalize_hue(h: f64) -> f64 {
+    if h.is_nan() {
+        return 0.0;
+    }
+    let h = h.rem_euclid(360.0);
+    // rem_euclid of a tiny negative value rounds up to 360.0
+    if h >= 360.0 {
+        0.0
+    } else {
+        h
+    }
+}
+
+/// Clamps a component into `[0.0, 1.0]`, NaN becomes `0.0`
+fn unit(value: f64) -> f64 {
+    if value.is_nan() {
+        0.0
+    } else {
+        value.clamp(0.0, 1.0)
+    }
+}
+
+/// Scales a `[0.0, 1.0]` component to a byte, rounding to nearest
+fn to_byte(value: f64) -> u8 {
+    (unit(value) * 255.0).round() as u8
+}
+
 pub fn hsv_from_rgb(rgb: &Rgb) -> Hsv {
     let r = rgb.r as f64 / 255.0;
     let g = rgb.g as f64 / 255.0;
@@ -11,7 +39,7 @@ pub fn hsv_from_rgb(rgb: &Rgb) -> Hsv {
     let h = if delta == 0.0 {
         0.0
     } else if max == r {
-        60.0 * (((g - b) / delta) % 6.0)
+        60.0 * ((g - b) / delta)
     } else if max == g {
         60.0 * (((b - r) / delta) + 2.0)
     } else {
@@ -20,34 +48,125 @@ pub fn hsv_from_rgb(rgb: &Rgb) -> Hsv {
     let s = if max == 0.0 { 0.0 } else { delta / max };
     let v = max;
     Hsv {
-        h,
+        h: normalize_hue(h),
         s,
         v,
         a: rgb.a as f64 / 255.0,
     }
 }
 
+/// Hue is taken modulo 360, saturation, value and alpha are clamped to `[0.0, 1.0]`
+/// and NaN components count as `0.0`.
 pub fn rgb_from_hsv(hsv: &Hsv) -> Rgb {
-    let c = hsv.v * hsv.s;
-    let x = c * (1.0 - ((hsv.h / 60.0) % 2.0 - 1.0).abs());
-    let m = hsv.v - c;
-    let (r, g, b) = if hsv.h < 60.0 {
-        (c, x, 0.0)
-    } else if hsv.h < 120.0 {
-        (x, c, 0.0)
-    } else if hsv.h < 180.0 {
-        (0.0, c, x)
-    } else if hsv.h < 240.0 {
-        (0.0, x, c)
-    } else if hsv.h < 300.0 {
-        (x, 0.0, c)
-    } else {
-        (c, 0.0, x)
+    let h = normalize_hue(hsv.h);
+    let s = unit(hsv.s);
+    let v = unit(hsv.v);
+    let c = v * s;
+    let x = c * (1.0 - ((h / 60.0) % 2.0 - 1.0).abs());
+    let m = v - c;
+    let (r, g, b) = match (h / 60.0) as u8 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
     };
     Rgb {
-        r: ((r + m) * 255.0) as u8,
-        g: ((g + m) * 255.0) as u8,
-        b: ((b + m) * 255.0) as u8,
-        a: (hsv.a * 255.0) as u8,
+        r: to_byte(r + m),
+        g: to_byte(g + m),
+        b: to_byte(b + m),
+        a: to_byte(hsv.a),
+    }
+}
+
+/// HSL (hue in degrees, saturation, lightness and alpha in `[0.0, 1.0]`) to HSV
+pub fn hsv_from_hsl(h: f64, s: f64, l: f64, a: f64) -> Hsv {
+    let s = unit(s);
+    let l = unit(l);
+    let v = l + s * l.min(1.0 - l);
+    Hsv {
+        h: normalize_hue(h),
+        s: if v == 0.0 { 0.0 } else { 2.0 * (1.0 - l / v) },
+        v,
+        a: unit(a),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rayon::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn test_hue_is_normalized() {
+        // Red with more blue than green used to give a negative hue
+        let hsv = hsv_from_rgb(&Rgb::from([255, 0, 10]));
+        assert!(hsv.h >= 0.0 && hsv.h < 360.0, "{}", hsv.h);
+        assert_eq!(normalize_hue(360.0), 0.0);
+        assert_eq!(normalize_hue(-30.0), 330.0);
+        assert_eq!(normalize_hue(-1e-20), 0.0);
+        assert_eq!(normalize_hue(725.0), 5.0);
+    }
+
+    #[test]
+    fn test_rgb_from_hsv_rounds() {
+        assert_eq!(
+            rgb_from_hsv(&Hsv::from([0.0, 0.0, 0.5])),
+            Rgb::from([128, 128, 128])
+        );
+        assert_eq!(
+            rgb_from_hsv(&Hsv::from([360.0, 1.0, 1.0])),
+            Rgb::from([255, 0, 0])
+        );
+        assert_eq!(
+            rgb_from_hsv(&Hsv::from([120.0, 1.0, 1.0, 0.5])),
+            Rgb::from([0, 255, 0, 128])
+        );
+    }
+
+    #[test]
+    fn test_rgb_from_hsv_nan() {
+        let hsv = Hsv {
+            h: f64::NAN,
+            s: f64::NAN,
+            v: 1.0,
+            a: f64::NAN,
+        };
+        assert_eq!(rgb_from_hsv(&hsv), Rgb::from([255, 255, 255, 0]));
+        let hsv = Hsv {
+            h: 90.0,
+            s: 1.0,
+            v: f64::NAN,
+            a: 1.0,
+        };
+        assert_eq!(rgb_from_hsv(&hsv), Rgb::from([0, 0, 0]));
+    }
+
+    #[test]
+    fn test_hsv_from_hsl() {
+        let hsv = hsv_from_hsl(120.0, 1.0, 0.25, 1.0);
+        assert_eq!(rgb_from_hsv(&hsv), Rgb::from([0, 128, 0]));
+        let hsv = hsv_from_hsl(0.0, 0.0, 1.0, 1.0);
+        assert_eq!(rgb_from_hsv(&hsv), Rgb::from([255, 255, 255]));
+        assert_eq!(hsv_from_hsl(0.0, 1.0, 0.0, 1.0).s, 0.0);
+    }
+
+    #[test]
+    fn test_round_trip_is_lossless() {
+        let failures = (0..1u32 << 24)
+            .into_par_iter()
+            .filter(|index| {
+                let rgb = Rgb::from([(index >> 16) as u8, (index >> 8) as u8, *index as u8]);
+                let hsv = hsv_from_rgb(&rgb);
+                !(0.0..360.0).contains(&hsv.h) || rgb_from_hsv(&hsv) != rgb
+            })
+            .count();
+        assert_eq!(failures, 0);
+        for a in 0..=255 {
+            let rgb = Rgb::from([12, 34, 56, a]);
+            assert_eq!(rgb_from_hsv(&hsv_from_rgb(&rgb)), rgb);
+        }
     }
 }
@@ -0,0 +1,264 @@
+use std::{fmt, str::FromStr};
+
+use super::{
+    conversion::{hsv_from_hsl, normalize_hue},
+    hsv::Hsv,
+    named::named_color,
+    rgb::Rgb,
+    Color,
+};
+
+/// Parses `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`, `rgb()`/`rgba()`, `hsv()`/`hsva()`,
+/// `hsl()`/`hsla()` and CSS color names. Functional arguments may be separated by
+/// commas, spaces or a `/` before alpha, and accept percentages.
+impl FromStr for Color {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        parse_color(text).map_err(|reason| format!("Invalid color \"{}\": {}", text, reason))
+    }
+}
+
+impl FromStr for Rgb {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Ok(text.parse::<Color>()?.get_rgb())
+    }
+}
+
+impl FromStr for Hsv {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Ok(text.parse::<Color>()?.get_hsv())
+    }
+}
+
+/// `#rrggbb`, or `#rrggbbaa` when not opaque
+impl fmt::Display for Rgb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b)?;
+        if self.a != 255 {
+            write!(f, "{:02x}", self.a)?;
+        }
+        Ok(())
+    }
+}
+
+/// `hsv(h, s, v)`, or `hsva(h, s, v, a)` when not opaque, with exact components
+impl fmt::Display for Hsv {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.a == 1.0 {
+            write!(f, "hsv({}, {}, {})", self.h, self.s, self.v)
+        } else {
+            write!(f, "hsva({}, {}, {}, {})", self.h, self.s, self.v, self.a)
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Color::Rgb(rgb) => rgb.fmt(f),
+            Color::Hsv(hsv) => hsv.fmt(f),
+        }
+    }
+}
+
+fn parse_color(text: &str) -> Result<Color, String> {
+    let text = text.trim().to_ascii_lowercase();
+    if let Some(hex) = text.strip_prefix('#') {
+        return parse_hex(hex).map(Color::Rgb);
+    }
+    if let Some((name, arguments)) = text.split_once('(') {
+        let arguments = arguments
+            .strip_suffix(')')
+            .ok_or("missing closing parenthesis")?;
+        let arguments: Vec<&str> = arguments
+            .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+            .filter(|argument| !argument.is_empty())
+            .collect();
+        return parse_function(name.trim(), &arguments);
+    }
+    named_color(&text)
+        .map(|rgba| Color::Rgb(Rgb::from(rgba)))
+        .ok_or_else(|| "unknown color name".to_string())
+}
+
+fn parse_hex(hex: &str) -> Result<Rgb, String> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err("not an hexadecimal color".to_string());
+    }
+    let digit = |index: usize| u8::from_str_radix(&hex[index..index + 1], 16).unwrap();
+    let byte = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16).unwrap();
+    match hex.len() {
+        3 | 4 => {
+            let mut rgba = [255; 4];
+            for (index, channel) in rgba.iter_mut().enumerate().take(hex.len()) {
+                *channel = digit(index) * 17;
+            }
+            Ok(Rgb::from(rgba))
+        }
+        6 | 8 => {
+            let mut rgba = [255; 4];
+            for (index, channel) in rgba.iter_mut().enumerate().take(hex.len() / 2) {
+                *channel = byte(index * 2);
+            }
+            Ok(Rgb::from(rgba))
+        }
+        _ => Err("expected 3, 4, 6 or 8 hexadecimal digits".to_string()),
+    }
+}
+
+fn parse_function(name: &str, arguments: &[&str]) -> Result<Color, String> {
+    let alpha = match arguments.len() {
+        3 => 1.0,
+        4 => parse_unit(arguments[3], "alpha")?,
+        count => return Err(format!("expected 3 or 4 arguments, got {}", count)),
+    };
+    match name {
+        "rgb" | "rgba" => {
+            let channel = |index: usize| parse_channel(arguments[index]);
+            Ok(Color::Rgb(Rgb {
+                r: channel(0)?,
+                g: channel(1)?,
+                b: channel(2)?,
+                a: (alpha * 255.0).round() as u8,
+            }))
+        }
+        "hsv" | "hsva" => Ok(Color::Hsv(Hsv {
+            h: parse_hue(arguments[0])?,
+            s: parse_unit(arguments[1], "saturation")?,
+            v: parse_unit(arguments[2], "value")?,
+            a: alpha,
+        })),
+        "hsl" | "hsla" => Ok(Color::Hsv(hsv_from_hsl(
+            parse_hue(arguments[0])?,
+            parse_unit(arguments[1], "saturation")?,
+            parse_unit(arguments[2], "lightness")?,
+            alpha,
+        ))),
+        _ => Err(format!("unknown color function \"{}\"", name)),
+    }
+}
+
+fn parse_number(text: &str) -> Result<f64, String> {
+    let number: f64 = text
+        .parse()
+        .map_err(|_| format!("\"{}\" is not a number", text))?;
+    if number.is_nan() {
+        return Err(format!("\"{}\" is not a number", text));
+    }
+    Ok(number)
+}
+
+/// `0` to `255`, or a percentage
+fn parse_channel(text: &str) -> Result<u8, String> {
+    let value = match text.strip_suffix('%') {
+        Some(percent) => parse_number(percent)? / 100.0 * 255.0,
+        None => parse_number(text)?,
+    };
+    if !(0.0..=255.0).contains(&value) {
+        return Err(format!("channel \"{}\" is not between 0 and 255", text));
+    }
+    Ok(value.round() as u8)
+}
+
+/// `0.0` to `1.0`, or a percentage
+fn parse_unit(text: &str, component: &str) -> Result<f64, String> {
+    let value = match text.strip_suffix('%') {
+        Some(percent) => parse_number(percent)? / 100.0,
+        None => parse_number(text)?,
+    };
+    if !(0.0..=1.0).contains(&value) {
+        return Err(format!(
+            "{} \"{}\" is not between 0.0 and 1.0",
+            component, text
+        ));
+    }
+    Ok(value)
+}
+
+/// Degrees, with an optional `deg` suffix, brought into `[0.0, 360.0)`
+fn parse_hue(text: &str) -> Result<f64, String> {
+    let value = parse_number(text.strip_suffix("deg").unwrap_or(text))?;
+    if value.is_infinite() {
+        return Err(format!("hue \"{}\" is not finite", text));
+    }
+    Ok(normalize_hue(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgb(text: &str) -> Rgb {
+        match text.parse::<Color>().unwrap() {
+            Color::Rgb(rgb) => rgb,
+            Color::Hsv(hsv) => panic!("{} parsed as {:?}", text, hsv),
+        }
+    }
+
+    #[test]
+    fn test_parse_hex() {
+        assert_eq!(rgb("#ff8000"), Rgb::from([255, 128, 0]));
+        assert_eq!(rgb("#FF800080"), Rgb::from([255, 128, 0, 128]));
+        assert_eq!(rgb("#f80"), Rgb::from([255, 136, 0]));
+        assert_eq!(rgb(" #f808 "), Rgb::from([255, 136, 0, 136]));
+    }
+
+    #[test]
+    fn test_parse_rgb_function() {
+        assert_eq!(rgb("rgb(10, 20, 30)"), Rgb::from([10, 20, 30]));
+        assert_eq!(rgb("rgba(10, 20, 30, 0.5)"), Rgb::from([10, 20, 30, 128]));
+        assert_eq!(rgb("rgb(100% 0% 50% / 25%)"), Rgb::from([255, 0, 128, 64]));
+    }
+
+    #[test]
+    fn test_parse_hsv_and_hsl() {
+        let color: Color = "hsv(200, 0.5, 70%)".parse().unwrap();
+        assert_eq!(color, Color::Hsv(Hsv::from([200.0, 0.5, 0.7])));
+        let color: Color = "hsva(360deg, 1, 1, 0.5)".parse().unwrap();
+        assert_eq!(color, Color::Hsv(Hsv::from([0.0, 1.0, 1.0, 0.5])));
+        let color: Color = "hsl(120, 100%, 25%)".parse().unwrap();
+        assert_eq!(color.get_rgb(), Rgb::from([0, 128, 0]));
+    }
+
+    #[test]
+    fn test_parse_named() {
+        assert_eq!(rgb("Tomato"), Rgb::from([255, 99, 71]));
+        assert_eq!(rgb("transparent"), Rgb::from([0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            "#12345".parse::<Color>().unwrap_err(),
+            "Invalid color \"#12345\": expected 3, 4, 6 or 8 hexadecimal digits"
+        );
+        assert!("#gg0000".parse::<Color>().is_err());
+        assert!("rgb(256, 0, 0)".parse::<Color>().is_err());
+        assert!("rgb(1, 2)".parse::<Color>().is_err());
+        assert!("rgb(1, 2, 3".parse::<Color>().is_err());
+        assert!("hsv(10, 1.5, 1)".parse::<Color>().is_err());
+        assert!("hsv(nan, 1, 1)".parse::<Color>().is_err());
+        assert!("cmyk(1, 2, 3)".parse::<Color>().is_err());
+        assert!("reddish".parse::<Color>().is_err());
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let colors = [
+            Color::Rgb(Rgb::from([1, 2, 3])),
+            Color::Rgb(Rgb::from([255, 254, 0, 7])),
+            Color::Hsv(Hsv::from([123.456, 0.1, 0.9])),
+            Color::Hsv(Hsv::from([0.0, 1.0 / 3.0, 1.0, 0.25])),
+        ];
+        for color in colors {
+            assert_eq!(color.to_string().parse::<Color>().unwrap(), color);
+        }
+        assert_eq!(Color::Rgb(Rgb::from([255, 0, 16])).to_string(), "#ff0010");
+        assert_eq!(Hsv::from([10.0, 0.5, 1.0]).to_string(), "hsv(10, 0.5, 1)");
+    }
+}
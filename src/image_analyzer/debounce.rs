@@ -0,0 +1,231 @@
+use std::collections::{HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use super::detector::{DetectionResult, DetectorSet};
+
+/// What a detection result must show for a frame to count, `{"min_pixels": 50}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    MinPixels(usize),
+    MinBlobs(usize),
+}
+
+/// Hysteresis over the last `window` frames: the detector turns on once the condition
+/// held in at least `on` of them and back off once it held in at most `off`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Debounce {
+    pub condition: Condition,
+    pub window: usize,
+    pub on: usize,
+    pub off: usize,
+}
+
+/// On/off state of one detector over time
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemporalFilter {
+    debounce: Debounce,
+    history: VecDeque<bool>,
+    on: bool,
+}
+
+/// Debounced states of the detectors of a set that have a `Debounce`
+#[derive(Debug, Clone, Default)]
+pub struct DebouncedStates {
+    filters: HashMap<String, TemporalFilter>,
+}
+
+impl Condition {
+    pub fn holds(&self, result: &DetectionResult) -> bool {
+        match self {
+            Condition::MinPixels(count) => result.pixels.points_count() >= *count,
+            Condition::MinBlobs(count) => result.blobs.len() >= *count,
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl Debounce {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.window == 0 {
+            return Err("window must be at least 1".to_string());
+        }
+        if self.on == 0 || self.on > self.window {
+            return Err("on must be between 1 and window".to_string());
+        }
+        if self.off >= self.on {
+            return Err("off must be below on".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[allow(dead_code)]
+impl TemporalFilter {
+    /// Starts off with an empty history
+    pub fn new(debounce: Debounce) -> Self {
+        Self {
+            history: VecDeque::with_capacity(debounce.window),
+            debounce,
+            on: false,
+        }
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    /// Records whether the condition held on a new frame and returns the state
+    pub fn push(&mut self, held: bool) -> bool {
+        if self.history.len() == self.debounce.window {
+            self.history.pop_front();
+        }
+        self.history.push_back(held);
+        let count = self.history.iter().filter(|held| **held).count();
+        if !self.on && count >= self.debounce.on {
+            self.on = true;
+        } else if self.on && count <= self.debounce.off {
+            self.on = false;
+        }
+        self.on
+    }
+
+    pub fn update(&mut self, result: &DetectionResult) -> bool {
+        self.push(self.debounce.condition.holds(result))
+    }
+}
+
+#[allow(dead_code)]
+impl DebouncedStates {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Debounced state of a detector, `None` before its first result or without a
+    /// `Debounce`
+    pub fn is_on(&self, name: &str) -> Option<bool> {
+        self.filters.get(name).map(TemporalFilter::is_on)
+    }
+
+    /// Feeds one frame of results of `set` and returns the detectors whose state
+    /// changed. A detector whose `Debounce` changed, after a reload, starts over.
+    pub fn update(
+        &mut self,
+        set: &DetectorSet,
+        results: &[DetectionResult],
+    ) -> Vec<(String, bool)> {
+        let mut changes = Vec::new();
+        for result in results {
+            let Some(debounce) = set.get(&result.name).and_then(|d| d.debounce.as_ref()) else {
+                self.filters.remove(&result.name);
+                continue;
+            };
+            let filter = self
+                .filters
+                .entry(result.name.clone())
+                .or_insert_with(|| TemporalFilter::new(debounce.clone()));
+            if filter.debounce != *debounce {
+                *filter = TemporalFilter::new(debounce.clone());
+            }
+            let before = filter.is_on();
+            if filter.update(result) != before {
+                changes.push((result.name.clone(), !before));
+            }
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{ImageBuffer, Rgba};
+
+    use super::*;
+    use crate::image_analyzer::{
+        color::rgb::Rgb,
+        detection::ColorDetection,
+        detector::{Detector, PostProcess, ScanStrategy},
+        ImageAnalyzer,
+    };
+
+    #[test]
+    fn test_hysteresis() {
+        let debounce = Debounce {
+            condition: Condition::MinPixels(1),
+            window: 5,
+            on: 3,
+            off: 1,
+        };
+        let mut filter = TemporalFilter::new(debounce.clone());
+        let frames = [1, 0, 0, 1, 0, 1, 1, 0, 1, 0, 0, 0, 0];
+        let states: Vec<u8> = frames
+            .iter()
+            .map(|&held| filter.push(held == 1) as u8)
+            .collect();
+        // On at the third hit in five frames, off once at most one hit is left
+        assert_eq!(states, vec![0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 0, 0]);
+
+        assert!(debounce.validate().is_ok());
+        let invalid = Debounce { off: 3, ..debounce };
+        assert_eq!(invalid.validate(), Err("off must be below on".to_string()));
+    }
+
+    #[test]
+    fn test_states_follow_the_set() {
+        let red = ColorDetection::rgb(Rgb::from([255, 0, 0]), Rgb::from([0, 0, 0, 0]));
+        let detector = |name: &str, debounce: Option<Debounce>| {
+            Detector::new(
+                name,
+                red.clone(),
+                vec![],
+                ScanStrategy::Full,
+                PostProcess::default(),
+            )
+            .unwrap()
+            .with_debounce(debounce)
+        };
+        let debounce = Debounce {
+            condition: Condition::MinPixels(2),
+            window: 2,
+            on: 2,
+            off: 0,
+        };
+        let set = DetectorSet::new(vec![
+            detector("debounced", Some(debounce)),
+            detector("raw", None),
+        ]);
+        let frame = |reds: u32| {
+            ImageAnalyzer::new(ImageBuffer::from_fn(4, 1, |x, _| {
+                if x < reds {
+                    Rgba([255, 0, 0, 255])
+                } else {
+                    Rgba([0, 0, 0, 255])
+                }
+            }))
+        };
+        let mut states = DebouncedStates::new();
+        let mut changes = Vec::new();
+        for reds in [3, 1, 2, 2, 0, 4, 0, 0] {
+            changes.push(states.update(&set, &set.run(&frame(reds))));
+        }
+        let on = |name: &str| vec![(name.to_string(), true)];
+        let off = |name: &str| vec![(name.to_string(), false)];
+        assert_eq!(
+            changes,
+            vec![
+                vec![],
+                vec![],
+                vec![],
+                on("debounced"),
+                vec![],
+                vec![],
+                vec![],
+                off("debounced")
+            ]
+        );
+        assert_eq!(states.is_on("debounced"), Some(false));
+        assert_eq!(states.is_on("raw"), None);
+    }
+}
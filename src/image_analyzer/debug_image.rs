@@ -0,0 +1,304 @@
+use std::path::Path;
+
+use image::{ImageBuffer, Rgba};
+
+use crate::data::{
+    better_call_zone::{Zone, ZoneManager},
+    point::Point,
+};
+
+use super::{blob::Blob, detector::DetectionResult, pixel::PixelVec, ImageAnalyzer};
+
+/// Colors handed out to results drawn without an explicit color
+pub const PALETTE: [Rgba<u8>; 6] = [
+    Rgba([255, 0, 255, 255]),
+    Rgba([0, 255, 0, 255]),
+    Rgba([0, 255, 255, 255]),
+    Rgba([255, 255, 0, 255]),
+    Rgba([255, 128, 0, 255]),
+    Rgba([128, 128, 255, 255]),
+];
+
+pub const GLYPH_WIDTH: u32 = 3;
+pub const GLYPH_HEIGHT: u32 = 5;
+
+/// 3x5 glyph rows, most significant of the 3 bits on the left
+pub fn glyph(c: char) -> [u8; 5] {
+    match c.to_ascii_uppercase() {
+        'A' => [2, 5, 7, 5, 5],
+        'B' => [6, 5, 6, 5, 6],
+        'C' => [3, 4, 4, 4, 3],
+        'D' => [6, 5, 5, 5, 6],
+        'E' => [7, 4, 6, 4, 7],
+        'F' => [7, 4, 6, 4, 4],
+        'G' => [3, 4, 5, 5, 3],
+        'H' => [5, 5, 7, 5, 5],
+        'I' => [7, 2, 2, 2, 7],
+        'J' => [1, 1, 1, 5, 2],
+        'K' => [5, 5, 6, 5, 5],
+        'L' => [4, 4, 4, 4, 7],
+        'M' => [5, 7, 7, 5, 5],
+        'N' => [6, 5, 5, 5, 5],
+        'O' => [2, 5, 5, 5, 2],
+        'P' => [6, 5, 6, 4, 4],
+        'Q' => [2, 5, 5, 6, 3],
+        'R' => [6, 5, 6, 5, 5],
+        'S' => [3, 4, 2, 1, 6],
+        'T' => [7, 2, 2, 2, 2],
+        'U' => [5, 5, 5, 5, 7],
+        'V' => [5, 5, 5, 5, 2],
+        'W' => [5, 5, 7, 7, 5],
+        'X' => [5, 5, 2, 5, 5],
+        'Y' => [5, 5, 2, 2, 2],
+        'Z' => [7, 1, 2, 4, 7],
+        '0' => [7, 5, 5, 5, 7],
+        '1' => [2, 6, 2, 2, 7],
+        '2' => [6, 1, 2, 4, 7],
+        '3' => [6, 1, 2, 1, 6],
+        '4' => [5, 5, 7, 1, 1],
+        '5' => [7, 4, 6, 1, 6],
+        '6' => [3, 4, 7, 5, 7],
+        '7' => [7, 1, 2, 2, 2],
+        '8' => [7, 5, 7, 5, 7],
+        '9' => [7, 5, 7, 1, 6],
+        '_' => [0, 0, 0, 0, 7],
+        '-' => [0, 0, 7, 0, 0],
+        '.' => [0, 0, 0, 0, 2],
+        ':' => [0, 2, 0, 2, 0],
+        ' ' => [0, 0, 0, 0, 0],
+        _ => [6, 1, 2, 0, 2],
+    }
+}
+
+enum Mark {
+    Pixels(Vec<Point>, Rgba<u8>),
+    Rect(Zone, Rgba<u8>),
+    Cross(Point, Rgba<u8>),
+    Label(Point, String, Rgba<u8>),
+}
+
+/// Frame annotated with detection results. The frame is dimmed so that highlighted
+/// pixels stand out, zones are outlined and labelled, and the mask view shows every
+/// highlighted pixel in white next to the frame.
+pub struct DebugImage {
+    frame: ImageBuffer<Rgba<u8>, Vec<u8>>,
+    marks: Vec<Mark>,
+    mask: bool,
+}
+
+#[allow(dead_code)]
+impl DebugImage {
+    pub fn new(analyzer: &ImageAnalyzer) -> Self {
+        Self {
+            frame: analyzer.image.clone(),
+            marks: Vec::new(),
+            mask: false,
+        }
+    }
+
+    /// Adds the black and white mask of highlighted pixels to the right of the frame
+    pub fn with_mask(mut self, mask: bool) -> Self {
+        self.mask = mask;
+        self
+    }
+
+    pub fn pixels(&mut self, pixels: &PixelVec, color: Rgba<u8>) -> &mut Self {
+        self.marks.push(Mark::Pixels(pixels.points(), color));
+        self
+    }
+
+    /// Outlines a zone, end excluded, with its label above it
+    pub fn zone(&mut self, zone: &Zone, label: &str, color: Rgba<u8>) -> &mut Self {
+        self.marks.push(Mark::Rect(zone.clone(), color));
+        if !label.is_empty() {
+            let y = zone.start.y.saturating_sub(GLYPH_HEIGHT + 2);
+            self.marks.push(Mark::Label(
+                Point::new(zone.start.x, y),
+                label.to_string(),
+                color,
+            ));
+        }
+        self
+    }
+
+    /// Outlines every zone of the manager, labelled `label#index`
+    pub fn zones(&mut self, manager: &ZoneManager, label: &str, color: Rgba<u8>) -> &mut Self {
+        for (index, zone) in manager.zones().iter().enumerate() {
+            self.zone(zone, &format!("{}#{}", label, index), color);
+        }
+        self
+    }
+
+    /// Outlines every blob and marks its centroid
+    pub fn blobs(&mut self, blobs: &[Blob], color: Rgba<u8>) -> &mut Self {
+        for blob in blobs {
+            self.marks.push(Mark::Rect(blob.zone.clone(), color));
+            self.marks.push(Mark::Cross(blob.centroid.clone(), color));
+        }
+        self
+    }
+
+    /// Pixels, blobs and merged zones of a detector result, zones labelled by name
+    pub fn result(&mut self, result: &DetectionResult, color: Rgba<u8>) -> &mut Self {
+        self.pixels(&result.pixels, color);
+        self.blobs(&result.blobs, color);
+        for zone in &result.zones {
+            self.zone(zone, &result.name, color);
+        }
+        self
+    }
+
+    /// Every result, colored from `PALETTE` in order
+    pub fn results(&mut self, results: &[DetectionResult]) -> &mut Self {
+        for (index, result) in results.iter().enumerate() {
+            self.result(result, PALETTE[index % PALETTE.len()]);
+        }
+        self
+    }
+
+    pub fn render(&self) -> ImageBuffer<Rgba<u8>, Vec<u8>> {
+        let (width, height) = self.frame.dimensions();
+        let mut image =
+            ImageBuffer::from_fn(if self.mask { width * 2 } else { width }, height, |x, y| {
+                if x >= width {
+                    return Rgba([0, 0, 0, 255]);
+                }
+                let [r, g, b, _] = self.frame.get_pixel(x, y).0;
+                Rgba([r / 3, g / 3, b / 3, 255])
+            });
+        let put = |image: &mut ImageBuffer<Rgba<u8>, Vec<u8>>, x: u32, y: u32, color| {
+            if x < width && y < height {
+                image.put_pixel(x, y, color);
+            }
+        };
+
+        for mark in &self.marks {
+            if let Mark::Pixels(points, color) = mark {
+                for point in points {
+                    put(&mut image, point.x, point.y, *color);
+                    if self.mask && point.x < width && point.y < height {
+                        image.put_pixel(width + point.x, point.y, Rgba([255, 255, 255, 255]));
+                    }
+                }
+            }
+        }
+        // Outlines and labels go over every highlighted pixel
+        for mark in &self.marks {
+            match mark {
+                Mark::Pixels(..) => {}
+                Mark::Rect(zone, color) => {
+                    let (x0, y0) = (zone.start.x, zone.start.y);
+                    let x1 = zone.end.x.saturating_sub(1).max(x0);
+                    let y1 = zone.end.y.saturating_sub(1).max(y0);
+                    for x in x0..=x1 {
+                        put(&mut image, x, y0, *color);
+                        put(&mut image, x, y1, *color);
+                    }
+                    for y in y0..=y1 {
+                        put(&mut image, x0, y, *color);
+                        put(&mut image, x1, y, *color);
+                    }
+                }
+                Mark::Cross(center, color) => {
+                    for offset in 0..5 {
+                        put(
+                            &mut image,
+                            (center.x + offset).saturating_sub(2),
+                            center.y,
+                            *color,
+                        );
+                        put(
+                            &mut image,
+                            center.x,
+                            (center.y + offset).saturating_sub(2),
+                            *color,
+                        );
+                    }
+                }
+                Mark::Label(origin, text, color) => {
+                    let text_width = text.chars().count() as u32 * (GLYPH_WIDTH + 1) + 1;
+                    for y in 0..GLYPH_HEIGHT + 2 {
+                        for x in 0..text_width {
+                            put(&mut image, origin.x + x, origin.y + y, Rgba([0, 0, 0, 255]));
+                        }
+                    }
+                    for (index, c) in text.chars().enumerate() {
+                        let left = origin.x + 1 + index as u32 * (GLYPH_WIDTH + 1);
+                        for (row, bits) in glyph(c).iter().enumerate() {
+                            for column in 0..GLYPH_WIDTH {
+                                if bits & (1 << (GLYPH_WIDTH - 1 - column)) != 0 {
+                                    put(
+                                        &mut image,
+                                        left + column,
+                                        origin.y + 1 + row as u32,
+                                        *color,
+                                    );
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        image
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        let path = path.as_ref();
+        self.render()
+            .save(path)
+            .map_err(|error| format!("Cannot write {}: {}", path.display(), error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image_analyzer::{
+        color::rgb::Rgb, detection::ColorDetection, detector::*, ImageZone,
+    };
+
+    #[test]
+    fn test_render_result() {
+        let analyzer = ImageAnalyzer::new(ImageBuffer::from_fn(60, 40, |x, y| {
+            if (20..30).contains(&x) && (20..30).contains(&y) {
+                Rgba([240, 0, 0, 255])
+            } else {
+                Rgba([90, 90, 90, 255])
+            }
+        }));
+        let detector = Detector::new(
+            "hp",
+            ColorDetection::rgb(Rgb::from([240, 0, 0]), Rgb::from([0, 0, 0, 0])),
+            vec![ImageZone::Full],
+            ScanStrategy::Full,
+            PostProcess {
+                blobs: Some(BlobOptions { min_size: 1 }),
+                merge_zones: true,
+            },
+        )
+        .unwrap();
+        let result = detector.run(&analyzer);
+        let magenta = PALETTE[0];
+
+        let mut debug = DebugImage::new(&analyzer).with_mask(true);
+        debug.results(&[result]);
+        let image = debug.render();
+        assert_eq!(image.dimensions(), (120, 40));
+        // Dimmed background, highlighted match, outline on the zone border
+        assert_eq!(*image.get_pixel(5, 35), Rgba([30, 30, 30, 255]));
+        assert_eq!(*image.get_pixel(22, 22), magenta);
+        assert_eq!(*image.get_pixel(29, 21), magenta);
+        // Mask view
+        assert_eq!(*image.get_pixel(60 + 22, 22), Rgba([255, 255, 255, 255]));
+        assert_eq!(*image.get_pixel(60 + 5, 35), Rgba([0, 0, 0, 255]));
+        // "HP" label drawn above the zone: H has its left column lit
+        assert_eq!(*image.get_pixel(21, 14), magenta);
+        assert_eq!(*image.get_pixel(22, 14), Rgba([0, 0, 0, 255]));
+
+        let path = std::env::temp_dir().join(format!("debug_{}.png", std::process::id()));
+        debug.save(&path).unwrap();
+        assert_eq!(image::open(&path).unwrap().to_rgba8(), image);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+
+use super::color::{hsv::Hsv, rgb::Rgb, Color};
+
+/// A color predicate: a reference color and how far a pixel may deviate from it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum ColorDetection {
+    Rgb {
+        color: Rgb,
+        tolerance: Rgb,
+    },
+    Hsv {
+        color: Hsv,
+        tolerance: Hsv,
+    },
+    /// Matches when any of the detections matches
+    Any(Vec<ColorDetection>),
+}
+#[allow(dead_code)]
+impl ColorDetection {
+    pub fn rgb(color: Rgb, tolerance: Rgb) -> Self {
+        ColorDetection::Rgb { color, tolerance }
+    }
+
+    pub fn hsv(color: Hsv, tolerance: Hsv) -> Self {
+        ColorDetection::Hsv { color, tolerance }
+    }
+
+    pub fn matches(&self, color: &Color) -> bool {
+        match self {
+            ColorDetection::Rgb {
+                color: reference,
+                tolerance,
+            } => reference.compare(&color.get_rgb(), *tolerance),
+            ColorDetection::Hsv {
+                color: reference,
+                tolerance,
+            } => reference.compare(&color.get_hsv(), *tolerance),
+            ColorDetection::Any(detections) => detections.iter().any(|d| d.matches(color)),
+        }
+    }
+
+    pub fn matches_rgb(&self, rgb: &Rgb) -> bool {
+        match self {
+            ColorDetection::Rgb {
+                color: reference,
+                tolerance,
+            } => reference.compare(rgb, *tolerance),
+            ColorDetection::Hsv {
+                color: reference,
+                tolerance,
+            } => reference.compare_from_rgb(rgb, *tolerance),
+            ColorDetection::Any(detections) => detections.iter().any(|d| d.matches_rgb(rgb)),
+        }
+    }
+
+    /// The single color detections this detection is made of
+    pub fn alternatives(&self) -> Vec<&ColorDetection> {
+        match self {
+            ColorDetection::Any(detections) => {
+                detections.iter().flat_map(|d| d.alternatives()).collect()
+            }
+            detection => vec![detection],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rgb_detection() {
+        let detection = ColorDetection::rgb(Rgb::from([200, 10, 10]), Rgb::from([10, 10, 10, 0]));
+        assert!(detection.matches_rgb(&Rgb::from([195, 5, 20])));
+        assert!(!detection.matches_rgb(&Rgb::from([185, 5, 20])));
+        assert!(!detection.matches_rgb(&Rgb::from([200, 10, 10, 0])));
+    }
+
+    #[test]
+    fn test_hsv_detection() {
+        let detection =
+            ColorDetection::hsv(Hsv::from([0.0, 1.0, 1.0]), Hsv::from([10.0, 0.2, 0.2, 0.0]));
+        assert!(detection.matches(&Color::Rgb(Rgb::from([255, 0, 0]))));
+        assert!(detection.matches(&Color::Rgb(Rgb::from([230, 20, 10]))));
+        assert!(!detection.matches(&Color::Rgb(Rgb::from([0, 255, 0]))));
+    }
+
+    #[test]
+    fn test_any_detection() {
+        let red = ColorDetection::rgb(Rgb::from([255, 0, 0]), Rgb::from([0, 0, 0, 0]));
+        let green = ColorDetection::hsv(
+            Hsv::from([120.0, 1.0, 1.0]),
+            Hsv::from([5.0, 0.1, 0.1, 0.0]),
+        );
+        let detection = ColorDetection::Any(vec![red, ColorDetection::Any(vec![green])]);
+        assert!(detection.matches_rgb(&Rgb::from([255, 0, 0])));
+        assert!(detection.matches_rgb(&Rgb::from([10, 250, 10])));
+        assert!(!detection.matches_rgb(&Rgb::from([0, 0, 255])));
+        assert_eq!(detection.alternatives().len(), 2);
+    }
+}
@@ -0,0 +1,395 @@
+use std::sync::{Arc, RwLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::data::{
+    better_call_zone::{Zone, ZoneManager},
+    normalized_zone::NormalizedZone,
+};
+
+use super::{
+    blob::{extract_blobs, Blob},
+    debounce::Debounce,
+    detection::ColorDetection,
+    lut::CompiledDetection,
+    pixel::PixelVec,
+    pyramid::ImagePyramid,
+    simd::RgbaMatcher,
+    ImageAnalyzer, ImageZone,
+};
+
+/// How a detector walks its zones
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScanStrategy {
+    /// Every pixel through `ColorDetection::matches`
+    #[default]
+    Full,
+    /// Coarse-to-fine over an image pyramid of the given depth
+    Pyramid { depth: u32 },
+    /// Raw RGBA comparison, RGB tolerance mode only
+    Simd,
+    /// Lookup tables built once by `ColorDetection::compile`
+    Compiled,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BlobOptions {
+    pub min_size: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PostProcess {
+    /// Groups detected pixels into blobs
+    #[serde(default)]
+    pub blobs: Option<BlobOptions>,
+    /// Merges the zones of touching blobs through a `ZoneManager`
+    #[serde(default)]
+    pub merge_zones: bool,
+}
+
+/// What a strategy needs precomputed before the first frame
+enum Prepared {
+    Nothing,
+    Matchers(Vec<RgbaMatcher>),
+    Compiled(CompiledDetection),
+}
+
+/// A named, ready-to-run detection: what to look for, where and how
+pub struct Detector {
+    pub name: String,
+    pub detection: ColorDetection,
+    /// Scanned zones, the whole image when empty along with `normalized_zones`
+    pub zones: Vec<ImageZone>,
+    /// Scanned zones resolved against the dimensions of each frame
+    pub normalized_zones: Vec<NormalizedZone>,
+    pub scan: ScanStrategy,
+    pub post: PostProcess,
+    /// Temporal filtering of the results, applied by `DebouncedStates`
+    pub debounce: Option<Debounce>,
+    prepared: Prepared,
+}
+
+#[allow(dead_code)]
+pub struct DetectionResult {
+    pub name: String,
+    pub pixels: PixelVec,
+    pub blobs: Vec<Blob>,
+    /// Merged blob zones when `PostProcess::merge_zones` is set
+    pub zones: Vec<Zone>,
+}
+
+#[allow(dead_code)]
+impl Detector {
+    pub fn new(
+        name: &str,
+        detection: ColorDetection,
+        zones: Vec<ImageZone>,
+        scan: ScanStrategy,
+        post: PostProcess,
+    ) -> Result<Self, String> {
+        let prepared = match scan {
+            ScanStrategy::Full | ScanStrategy::Pyramid { .. } => Prepared::Nothing,
+            ScanStrategy::Simd => Prepared::Matchers(
+                detection
+                    .rgba_matchers()
+                    .ok_or("simd scan needs the rgb tolerance mode")?,
+            ),
+            ScanStrategy::Compiled => Prepared::Compiled(detection.compile()),
+        };
+        Ok(Self {
+            name: name.to_string(),
+            detection,
+            zones,
+            normalized_zones: Vec::new(),
+            scan,
+            post,
+            debounce: None,
+            prepared,
+        })
+    }
+
+    pub fn with_normalized_zones(mut self, zones: Vec<NormalizedZone>) -> Self {
+        self.normalized_zones = zones;
+        self
+    }
+
+    pub fn with_debounce(mut self, debounce: Option<Debounce>) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    pub fn run(&self, analyzer: &ImageAnalyzer) -> DetectionResult {
+        let (width, height) = analyzer.image.dimensions();
+        let zones = if self.zones.is_empty() && self.normalized_zones.is_empty() {
+            vec![ImageZone::Full]
+        } else {
+            // Normalized zones empty at this resolution are skipped
+            let normalized = self
+                .normalized_zones
+                .iter()
+                .filter_map(|zone| zone.resolve(width, height))
+                .map(|zone| ImageZone::from(&zone));
+            // Clipped to the frame, a zone may exceed a smaller capture or a reloaded config
+            self.zones
+                .iter()
+                .cloned()
+                .chain(normalized)
+                .filter_map(|zone| zone.clip(width, height))
+                .collect::<Vec<_>>()
+        };
+        // Other zones would only scan the whole image again
+        let zones = if zones.contains(&ImageZone::Full) {
+            vec![ImageZone::Full]
+        } else {
+            zones
+        };
+        let pyramid = match self.scan {
+            ScanStrategy::Pyramid { depth } => Some(ImagePyramid::new(&analyzer.image, depth)),
+            _ => None,
+        };
+
+        let mut pixels = PixelVec::new();
+        for (index, zone) in zones.iter().cloned().enumerate() {
+            let found = match (&self.prepared, &pyramid) {
+                (Prepared::Matchers(matchers), _) => analyzer.simd_detect_pixels(zone, matchers),
+                (Prepared::Compiled(compiled), _) => analyzer.detect_compiled(zone, compiled),
+                (Prepared::Nothing, Some(pyramid)) => {
+                    analyzer
+                        .pyramid_detect(pyramid, zone, &self.detection)
+                        .pixels
+                }
+                (Prepared::Nothing, None) => analyzer.detect(zone, &self.detection),
+            };
+            // Points of overlapping zones are only counted once
+            let scanned = &zones[..index];
+            for pixel in found.pixels() {
+                for point in &pixel.points {
+                    if !scanned.iter().any(|zone| zone.contains(point)) {
+                        pixels.push((pixel.color.clone(), point.clone()));
+                    }
+                }
+            }
+        }
+
+        let blobs = match (&self.post.blobs, self.post.merge_zones) {
+            (Some(options), _) => extract_blobs(&pixels.points(), options.min_size),
+            (None, true) => extract_blobs(&pixels.points(), 1),
+            (None, false) => Vec::new(),
+        };
+        let zones = if self.post.merge_zones {
+            let mut manager = ZoneManager::new(width, height);
+            for blob in &blobs {
+                manager.add_zone(blob.zone.clone());
+            }
+            manager.merge_overlapping_zones();
+            manager.zones().clone()
+        } else {
+            Vec::new()
+        };
+        let blobs = if self.post.blobs.is_some() {
+            blobs
+        } else {
+            Vec::new()
+        };
+
+        DetectionResult {
+            name: self.name.clone(),
+            pixels,
+            blobs,
+            zones,
+        }
+    }
+}
+
+/// Detectors loaded together, run in order on a frame
+pub struct DetectorSet {
+    detectors: Vec<Detector>,
+}
+#[allow(dead_code)]
+impl DetectorSet {
+    pub fn new(detectors: Vec<Detector>) -> Self {
+        Self { detectors }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Detector> {
+        self.detectors.iter().find(|detector| detector.name == name)
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Detector> {
+        self.detectors.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.detectors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.detectors.is_empty()
+    }
+
+    pub fn run(&self, analyzer: &ImageAnalyzer) -> Vec<DetectionResult> {
+        self.detectors
+            .iter()
+            .map(|detector| detector.run(analyzer))
+            .collect()
+    }
+}
+
+/// A detector set that can be swapped while frames are being analyzed. A frame takes a
+/// snapshot once and keeps running on it even if a new set is stored meanwhile.
+#[derive(Clone)]
+pub struct SharedDetectors(Arc<RwLock<Arc<DetectorSet>>>);
+#[allow(dead_code)]
+impl SharedDetectors {
+    pub fn new(set: DetectorSet) -> Self {
+        Self(Arc::new(RwLock::new(Arc::new(set))))
+    }
+
+    pub fn snapshot(&self) -> Arc<DetectorSet> {
+        self.0.read().unwrap().clone()
+    }
+
+    pub fn replace(&self, set: DetectorSet) {
+        *self.0.write().unwrap() = Arc::new(set);
+    }
+}
+
+#[allow(dead_code)]
+impl ImageAnalyzer {
+    /// Runs the attached detector set on the current frame, nothing when none is attached
+    pub fn run_detectors(&self) -> Vec<DetectionResult> {
+        match &self.detectors {
+            Some(shared) => shared.snapshot().run(self),
+            None => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{ImageBuffer, Rgba};
+
+    use super::*;
+    use crate::{data::point::Point, image_analyzer::color::rgb::Rgb};
+
+    fn analyzer() -> ImageAnalyzer {
+        ImageAnalyzer::new(ImageBuffer::from_fn(80, 60, |x, y| {
+            if (10..30).contains(&x) && (10..20).contains(&y) {
+                Rgba([200, 30, 30, 255])
+            } else if (30..40).contains(&x) && (20..25).contains(&y) {
+                Rgba([205, 25, 30, 255])
+            } else if (60..70).contains(&x) && (40..50).contains(&y) {
+                Rgba([210, 30, 30, 255])
+            } else {
+                Rgba([0, 0, 0, 255])
+            }
+        }))
+    }
+
+    fn red() -> ColorDetection {
+        ColorDetection::Any(vec![
+            ColorDetection::rgb(Rgb::from([200, 30, 30]), Rgb::from([5, 5, 5, 0])),
+            ColorDetection::rgb(Rgb::from([210, 30, 30]), Rgb::from([0, 0, 0, 0])),
+        ])
+    }
+
+    #[test]
+    fn test_strategies_agree() {
+        let analyzer = analyzer();
+        let post = PostProcess {
+            blobs: Some(BlobOptions { min_size: 1 }),
+            merge_zones: true,
+        };
+        for scan in [
+            ScanStrategy::Full,
+            ScanStrategy::Pyramid { depth: 2 },
+            ScanStrategy::Simd,
+        ] {
+            let detector = Detector::new("red", red(), vec![], scan.clone(), post.clone()).unwrap();
+            let result = detector.run(&analyzer);
+            assert_eq!(result.pixels.points_count(), 200 + 50 + 100, "{:?}", scan);
+            assert_eq!(result.blobs.len(), 2, "{:?}", scan);
+            assert_eq!(result.zones.len(), 2, "{:?}", scan);
+            assert_eq!(result.zones[0].start, Point::new(10, 10));
+            assert_eq!(result.zones[0].end, Point::new(40, 25));
+        }
+    }
+
+    #[test]
+    fn test_detector_zones() {
+        let analyzer = analyzer();
+        let zones = vec![
+            ImageZone::Partial(Point::new(0, 0), Point::new(20, 60)),
+            ImageZone::Partial(Point::new(65, 0), Point::new(80, 60)),
+        ];
+        let detector = Detector::new(
+            "red",
+            red(),
+            zones,
+            ScanStrategy::Full,
+            PostProcess::default(),
+        )
+        .unwrap();
+        let result = detector.run(&analyzer);
+        assert_eq!(result.pixels.points_count(), 100 + 50);
+        assert!(result.blobs.is_empty());
+        assert!(result.zones.is_empty());
+    }
+
+    #[test]
+    fn test_zones_clipped_to_frame() {
+        let analyzer = analyzer();
+        let zones = vec![
+            ImageZone::Partial(Point::new(50, 0), Point::new(200, 200)),
+            ImageZone::Partial(Point::new(100, 100), Point::new(120, 120)),
+        ];
+        for scan in [ScanStrategy::Full, ScanStrategy::Compiled] {
+            let detector =
+                Detector::new("red", red(), zones.clone(), scan, PostProcess::default()).unwrap();
+            assert_eq!(detector.run(&analyzer).pixels.points_count(), 100);
+        }
+    }
+
+    #[test]
+    fn test_overlapping_zones_count_once() {
+        let analyzer = analyzer();
+        let overlapping = vec![
+            ImageZone::Partial(Point::new(0, 0), Point::new(20, 60)),
+            ImageZone::Partial(Point::new(10, 0), Point::new(35, 60)),
+        ];
+        let with_full = vec![
+            ImageZone::Partial(Point::new(0, 0), Point::new(20, 60)),
+            ImageZone::Full,
+        ];
+        for (zones, count) in [(overlapping, 200 + 25), (with_full, 200 + 50 + 100)] {
+            let post = PostProcess {
+                blobs: Some(BlobOptions { min_size: 1 }),
+                merge_zones: false,
+            };
+            let detector = Detector::new("red", red(), zones, ScanStrategy::Full, post).unwrap();
+            let result = detector.run(&analyzer);
+            assert_eq!(result.pixels.points_count(), count);
+            let blob_sizes: usize = result.blobs.iter().map(|blob| blob.size()).sum();
+            assert_eq!(blob_sizes, count);
+        }
+    }
+
+    #[test]
+    fn test_simd_needs_rgb() {
+        let detection = ColorDetection::hsv(
+            crate::image_analyzer::color::hsv::Hsv::from([0.0, 1.0, 1.0]),
+            crate::image_analyzer::color::hsv::Hsv::from([5.0, 0.1, 0.1, 0.0]),
+        );
+        let detector = Detector::new(
+            "hsv",
+            detection,
+            vec![],
+            ScanStrategy::Simd,
+            PostProcess::default(),
+        );
+        assert!(detector.is_err());
+    }
+}
@@ -0,0 +1,180 @@
+use image::{ImageBuffer, Rgba};
+
+use crate::data::{
+    better_call_zone::{Zone, ZoneManager},
+    point::Point,
+};
+
+use super::{color::rgb::Rgb, detection::ColorDetection, pixel::PixelVec, ImageAnalyzer};
+
+/// Side of the square tiles changed pixels are grouped by before merging
+const DIFF_TILE: u32 = 16;
+
+/// Pixels that changed between two frames
+pub struct FrameDiff {
+    pub changed: Vec<Point>,
+    /// Dirty rectangles (end excluded) covering every changed pixel
+    pub zones: ZoneManager,
+}
+#[allow(dead_code)]
+impl FrameDiff {
+    /// Compares two frames pixel by pixel. Without a tolerance any byte difference
+    /// counts as a change, otherwise a pixel changes when `Rgb::compare` fails. Every
+    /// pixel of a frame resized since the previous one is changed.
+    pub fn compute(
+        previous: &ImageBuffer<Rgba<u8>, Vec<u8>>,
+        current: &ImageBuffer<Rgba<u8>, Vec<u8>>,
+        tolerance: Option<Rgb>,
+    ) -> Self {
+        let resized = previous.dimensions() != current.dimensions();
+        let (width, height) = current.dimensions();
+        let columns = width.div_ceil(DIFF_TILE);
+        let rows = height.div_ceil(DIFF_TILE);
+        // Bounding box of the changed pixels of every tile
+        let mut tiles: Vec<Option<(u32, u32, u32, u32)>> = vec![None; (columns * rows) as usize];
+        let mut changed = Vec::new();
+
+        for (x, y, pixel) in current.enumerate_pixels() {
+            let is_changed = resized || {
+                let before = previous.get_pixel(x, y);
+                match tolerance {
+                    None => before != pixel,
+                    Some(tolerance) => !Rgb::from(before.0).compare(&Rgb::from(pixel.0), tolerance),
+                }
+            };
+            if !is_changed {
+                continue;
+            }
+            changed.push(Point { x, y });
+            let tile = &mut tiles[((y / DIFF_TILE) * columns + x / DIFF_TILE) as usize];
+            *tile = match *tile {
+                None => Some((x, y, x + 1, y + 1)),
+                Some((min_x, min_y, max_x, max_y)) => Some((
+                    min_x.min(x),
+                    min_y.min(y),
+                    max_x.max(x + 1),
+                    max_y.max(y + 1),
+                )),
+            };
+        }
+
+        let mut zones = ZoneManager::new(width, height);
+        for (min_x, min_y, max_x, max_y) in tiles.into_iter().flatten() {
+            zones.add_zone(Zone::new(
+                Point::new(min_x, min_y),
+                Point::new(max_x, max_y),
+            ));
+        }
+        zones.merge_overlapping_zones();
+        Self { changed, zones }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty()
+    }
+}
+
+#[allow(dead_code)]
+impl ImageAnalyzer {
+    /// Differences between the previous frame and the current one, `None` before the
+    /// second frame
+    pub fn frame_diff(&self, tolerance: Option<Rgb>) -> Option<FrameDiff> {
+        self.previous
+            .as_ref()
+            .map(|previous| FrameDiff::compute(previous, &self.image, tolerance))
+    }
+
+    /// Same as [`ImageAnalyzer::detect`] restricted to the dirty rectangles of a diff
+    pub fn detect_changed(&self, diff: &FrameDiff, detection: &ColorDetection) -> PixelVec {
+        let mut points = PixelVec::new();
+        self.detect_zones(
+            diff.zones.zones().iter().map(|zone| zone.into()).collect(),
+            |color, point| {
+                if detection.matches(&color) {
+                    points.push((color, point));
+                }
+                None
+            },
+        );
+        points
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame() -> ImageBuffer<Rgba<u8>, Vec<u8>> {
+        ImageBuffer::from_pixel(120, 80, Rgba([20, 20, 20, 255]))
+    }
+
+    #[test]
+    fn test_frame_diff_zones() {
+        let previous = frame();
+        let mut current = frame();
+        for y in 10..30 {
+            for x in 5..40 {
+                current.put_pixel(x, y, Rgba([200, 0, 0, 255]));
+            }
+        }
+        current.put_pixel(100, 70, Rgba([0, 200, 0, 255]));
+
+        let diff = FrameDiff::compute(&previous, &current, None);
+        assert_eq!(diff.changed.len(), 35 * 20 + 1);
+        let zones = diff.zones.zones();
+        assert_eq!(zones.len(), 2);
+        assert_eq!(zones[0].start, Point::new(5, 10));
+        assert_eq!(zones[0].end, Point::new(40, 30));
+        assert_eq!(zones[1].start, Point::new(100, 70));
+        assert_eq!(zones[1].end, Point::new(101, 71));
+    }
+
+    #[test]
+    fn test_frame_diff_tolerance() {
+        let previous = frame();
+        let mut current = frame();
+        current.put_pixel(3, 3, Rgba([24, 18, 20, 255]));
+        current.put_pixel(50, 50, Rgba([90, 20, 20, 255]));
+
+        assert_eq!(
+            FrameDiff::compute(&previous, &current, None).changed.len(),
+            2
+        );
+        let diff = FrameDiff::compute(&previous, &current, Some(Rgb::from([5, 5, 5, 0])));
+        assert_eq!(diff.changed, vec![Point::new(50, 50)]);
+    }
+
+    #[test]
+    fn test_frame_diff_resized() {
+        let previous = ImageBuffer::from_pixel(40, 30, Rgba([20, 20, 20, 255]));
+        let mut analyzer = ImageAnalyzer::new(previous);
+        analyzer.next_frame(frame());
+
+        let diff = analyzer.frame_diff(None).unwrap();
+        assert_eq!(diff.changed.len(), 120 * 80);
+        assert_eq!(
+            diff.zones.zones(),
+            &vec![Zone::new(Point::new(0, 0), Point::new(120, 80))]
+        );
+    }
+
+    #[test]
+    fn test_detect_changed() {
+        let mut analyzer = ImageAnalyzer::new(frame());
+        assert!(analyzer.frame_diff(None).is_none());
+
+        let mut current = frame();
+        current.put_pixel(60, 20, Rgba([200, 0, 0, 255]));
+        current.put_pixel(61, 20, Rgba([200, 0, 0, 255]));
+        analyzer.next_frame(current);
+
+        let diff = analyzer.frame_diff(None).unwrap();
+        let background = ColorDetection::rgb(Rgb::from([20, 20, 20]), Rgb::from([0, 0, 0, 0]));
+        let red = ColorDetection::rgb(Rgb::from([200, 0, 0]), Rgb::from([0, 0, 0, 0]));
+        assert_eq!(analyzer.detect_changed(&diff, &red).points_count(), 2);
+        assert_eq!(
+            analyzer.detect_changed(&diff, &background).points_count(),
+            0
+        );
+    }
+}
@@ -0,0 +1,348 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use image::{ImageBuffer, ImageFormat, Rgba};
+
+use super::{
+    detector::{DetectionResult, SharedDetectors},
+    recording::Replay,
+    ImageAnalyzer,
+};
+
+/// One captured image and when it was captured, relative to the start of the source
+pub struct Frame {
+    pub image: ImageBuffer<Rgba<u8>, Vec<u8>>,
+    pub timestamp: Duration,
+}
+
+/// Where frames come from, a screen capture backend or a file-backed stand-in
+pub trait FrameSource {
+    /// The next frame, `None` once the source is exhausted
+    fn next_frame(&mut self) -> Option<Result<Frame, String>>;
+
+    /// Size of every frame of the source
+    #[allow(dead_code)]
+    fn dimensions(&self) -> (u32, u32);
+}
+
+/// Images of a directory in file name order, timestamped at a fixed interval
+pub struct DirectorySource {
+    files: Vec<PathBuf>,
+    next: usize,
+    interval: Duration,
+    dimensions: (u32, u32),
+}
+
+/// Frames of a recording written by a `Recorder`
+pub struct ReplaySource {
+    replay: Replay,
+    next: usize,
+}
+
+/// Frames produced in memory by a closure, `None` ends the source
+pub struct GeneratorSource<F> {
+    generator: F,
+    index: u64,
+    interval: Duration,
+    dimensions: (u32, u32),
+}
+
+fn check_dimensions(frame: Frame, dimensions: (u32, u32)) -> Result<Frame, String> {
+    if frame.image.dimensions() != dimensions {
+        return Err(format!(
+            "Frame of {}x{} in a {}x{} source",
+            frame.image.width(),
+            frame.image.height(),
+            dimensions.0,
+            dimensions.1
+        ));
+    }
+    Ok(frame)
+}
+
+#[allow(dead_code)]
+impl DirectorySource {
+    /// Every file of `directory` with an image extension, 30 frames per second
+    pub fn open<P: AsRef<Path>>(directory: P) -> Result<Self, String> {
+        let directory = directory.as_ref();
+        let mut files: Vec<PathBuf> = fs::read_dir(directory)
+            .map_err(|error| format!("Cannot read {}: {}", directory.display(), error))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && ImageFormat::from_path(path).is_ok())
+            .collect();
+        files.sort();
+        let first = files
+            .first()
+            .ok_or(format!("No image in {}", directory.display()))?;
+        let dimensions = image::image_dimensions(first)
+            .map_err(|error| format!("Cannot read {}: {}", first.display(), error))?;
+        Ok(Self {
+            files,
+            next: 0,
+            interval: Duration::from_secs(1) / 30,
+            dimensions,
+        })
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+impl FrameSource for DirectorySource {
+    fn next_frame(&mut self) -> Option<Result<Frame, String>> {
+        let path = self.files.get(self.next)?;
+        let frame = image::open(path)
+            .map_err(|error| format!("Cannot read {}: {}", path.display(), error))
+            .map(|image| Frame {
+                image: image.to_rgba8(),
+                timestamp: self.interval * self.next as u32,
+            })
+            .and_then(|frame| check_dimensions(frame, self.dimensions));
+        self.next += 1;
+        Some(frame)
+    }
+
+    fn dimensions(&self) -> (u32, u32) {
+        self.dimensions
+    }
+}
+
+#[allow(dead_code)]
+impl ReplaySource {
+    pub fn new(replay: Replay) -> Self {
+        Self { replay, next: 0 }
+    }
+}
+
+impl FrameSource for ReplaySource {
+    fn next_frame(&mut self) -> Option<Result<Frame, String>> {
+        if self.next >= self.replay.len() {
+            return None;
+        }
+        self.next += 1;
+        let frame = self.replay.frame(self.next - 1);
+        Some(frame.and_then(|frame| check_dimensions(frame, self.dimensions())))
+    }
+
+    fn dimensions(&self) -> (u32, u32) {
+        self.replay
+            .manifest()
+            .frames
+            .first()
+            .map_or((0, 0), |frame| (frame.width, frame.height))
+    }
+}
+
+#[allow(dead_code)]
+impl<F> GeneratorSource<F>
+where
+    F: FnMut(u64) -> Option<ImageBuffer<Rgba<u8>, Vec<u8>>>,
+{
+    /// `generator` receives the frame index, frames are `interval` apart
+    pub fn new(width: u32, height: u32, interval: Duration, generator: F) -> Self {
+        Self {
+            generator,
+            index: 0,
+            interval,
+            dimensions: (width, height),
+        }
+    }
+}
+
+impl<F> FrameSource for GeneratorSource<F>
+where
+    F: FnMut(u64) -> Option<ImageBuffer<Rgba<u8>, Vec<u8>>>,
+{
+    fn next_frame(&mut self) -> Option<Result<Frame, String>> {
+        let image = (self.generator)(self.index)?;
+        let frame = Frame {
+            image,
+            timestamp: self.interval * self.index as u32,
+        };
+        self.index += 1;
+        Some(check_dimensions(frame, self.dimensions))
+    }
+
+    fn dimensions(&self) -> (u32, u32) {
+        self.dimensions
+    }
+}
+
+/// Pulls frames from a source into an `ImageAnalyzer` and runs its detectors on each
+pub struct FrameLoop<S> {
+    source: S,
+    detectors: SharedDetectors,
+    max_frames: Option<usize>,
+}
+
+#[allow(dead_code)]
+impl<S: FrameSource> FrameLoop<S> {
+    pub fn new(source: S, detectors: SharedDetectors) -> Self {
+        Self {
+            source,
+            detectors,
+            max_frames: None,
+        }
+    }
+
+    pub fn max_frames(mut self, max_frames: usize) -> Self {
+        self.max_frames = Some(max_frames);
+        self
+    }
+
+    /// Runs until the source is exhausted, `max_frames` is reached or `on_frame`
+    /// returns false. Returns the number of frames analyzed.
+    pub fn run<F>(&mut self, mut on_frame: F) -> Result<usize, String>
+    where
+        F: FnMut(&ImageAnalyzer, Duration, &[DetectionResult]) -> bool,
+    {
+        let mut analyzer: Option<ImageAnalyzer> = None;
+        let mut count = 0;
+        while self.max_frames.is_none_or(|max| count < max) {
+            let Some(frame) = self.source.next_frame() else {
+                break;
+            };
+            let frame = frame?;
+            let analyzer = match analyzer.as_mut() {
+                Some(analyzer) => {
+                    analyzer.next_frame(frame.image);
+                    analyzer
+                }
+                None => analyzer
+                    .insert(ImageAnalyzer::new(frame.image).with_detectors(self.detectors.clone())),
+            };
+            count += 1;
+            let results = analyzer.run_detectors();
+            if !on_frame(analyzer, frame.timestamp, &results) {
+                break;
+            }
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image_analyzer::{
+        color::rgb::Rgb,
+        detection::ColorDetection,
+        detector::{Detector, DetectorSet, PostProcess, ScanStrategy},
+        recording::{FrameFormat, Recorder},
+    };
+
+    /// A red pixel walking along the first row
+    fn frame(index: u64) -> ImageBuffer<Rgba<u8>, Vec<u8>> {
+        ImageBuffer::from_fn(16, 4, |x, y| {
+            if x as u64 == index && y == 0 {
+                Rgba([255, 0, 0, 255])
+            } else {
+                Rgba([0, 0, 0, 255])
+            }
+        })
+    }
+
+    fn detectors() -> SharedDetectors {
+        let red = ColorDetection::rgb(Rgb::from([255, 0, 0]), Rgb::from([0, 0, 0, 0]));
+        let detector = Detector::new(
+            "red",
+            red,
+            vec![],
+            ScanStrategy::Full,
+            PostProcess::default(),
+        )
+        .unwrap();
+        SharedDetectors::new(DetectorSet::new(vec![detector]))
+    }
+
+    /// Position of the red pixel and timestamp of every frame of the source
+    fn positions<S: FrameSource>(source: S) -> Vec<(u32, u128)> {
+        let mut positions = Vec::new();
+        let count = FrameLoop::new(source, detectors())
+            .run(|_, timestamp, results| {
+                positions.push((results[0].pixels.points()[0].x, timestamp.as_millis()));
+                true
+            })
+            .unwrap();
+        assert_eq!(count, positions.len());
+        positions
+    }
+
+    #[test]
+    fn test_sources_feed_the_loop() {
+        let generator = GeneratorSource::new(16, 4, Duration::from_millis(10), |index| {
+            (index < 3).then(|| frame(index))
+        });
+        assert_eq!(generator.dimensions(), (16, 4));
+        assert_eq!(positions(generator), vec![(0, 0), (1, 10), (2, 20)]);
+
+        let directory = std::env::temp_dir().join(format!("frames_{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        for index in 0..3 {
+            frame(index)
+                .save(directory.join(format!("{:03}.png", 2 - index)))
+                .unwrap();
+        }
+        fs::write(directory.join("notes.txt"), "not a frame").unwrap();
+        let source = DirectorySource::open(&directory)
+            .unwrap()
+            .with_interval(Duration::from_millis(50));
+        assert_eq!(source.len(), 3);
+        assert_eq!(positions(source), vec![(2, 0), (1, 50), (0, 100)]);
+        fs::remove_dir_all(&directory).unwrap();
+
+        let directory = std::env::temp_dir().join(format!("replay_{}", std::process::id()));
+        let mut recorder = Recorder::create(&directory, FrameFormat::Raw).unwrap();
+        for index in 0..3 {
+            recorder
+                .record(&frame(index), Duration::from_millis(index * 7))
+                .unwrap();
+        }
+        let source = ReplaySource::new(Replay::open(&directory).unwrap());
+        assert_eq!(source.dimensions(), (16, 4));
+        assert_eq!(positions(source), vec![(0, 0), (1, 7), (2, 14)]);
+        recorder
+            .record(&ImageBuffer::new(8, 4), Duration::from_millis(21))
+            .unwrap();
+        let mut source = ReplaySource::new(Replay::open(&directory).unwrap());
+        let last = std::iter::from_fn(|| source.next_frame()).last().unwrap();
+        assert_eq!(
+            last.err(),
+            Some("Frame of 8x4 in a 16x4 source".to_string())
+        );
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_loop_limits() {
+        let endless =
+            || GeneratorSource::new(16, 4, Duration::ZERO, |index| Some(frame(index % 16)));
+        let mut frame_loop = FrameLoop::new(endless(), detectors()).max_frames(5);
+        assert_eq!(frame_loop.run(|_, _, _| true), Ok(5));
+        let mut frame_loop = FrameLoop::new(endless(), detectors());
+        assert_eq!(
+            frame_loop.run(|analyzer, _, _| analyzer.previous.is_none()),
+            Ok(2)
+        );
+
+        let wrong_size = GeneratorSource::new(8, 4, Duration::ZERO, |index| Some(frame(index)));
+        let mut frame_loop = FrameLoop::new(wrong_size, detectors());
+        assert_eq!(
+            frame_loop.run(|_, _, _| true),
+            Err("Frame of 16x4 in a 8x4 source".to_string())
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use serde::{Deserialize, Serialize};
+
+use super::{color::rgb::Rgb, detection::ColorDetection, ImageAnalyzer, ImageZone};
+
+/// Side the bar fills from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FillDirection {
+    LeftToRight,
+    RightToLeft,
+    TopToBottom,
+    BottomToTop,
+}
+
+/// An HP/MP style bar: the part of `zone` matching `fill` grows in `direction`, the
+/// rest matches `empty`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gauge {
+    pub zone: ImageZone,
+    pub direction: FillDirection,
+    pub fill: ColorDetection,
+    pub empty: ColorDetection,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GaugeReading {
+    /// Filled part of the bar, from 0.0 to 1.0
+    pub fill: f64,
+    /// From 0.0 to 1.0, lowered by slices that are neither fill nor empty (text,
+    /// borders) and by slices contradicting a single fill boundary
+    pub confidence: f64,
+}
+
+/// Fill and empty pixels of a slice across the bar
+#[derive(Clone, Copy)]
+struct Slice {
+    fill: u32,
+    empty: u32,
+}
+
+impl Slice {
+    fn known(&self) -> bool {
+        self.fill + self.empty > 0
+    }
+
+    fn filled(&self) -> bool {
+        self.fill > self.empty
+    }
+}
+
+#[allow(dead_code)]
+impl ImageAnalyzer {
+    /// Reads the bar slice by slice along its direction. A slice votes filled or empty
+    /// with its majority, so text and gradients only lose the pixels they cover, and
+    /// slices without fill or empty pixels, like borders at the ends, are left out of
+    /// the bar length. The fill boundary is where the fewest votes disagree.
+    pub fn read_gauge(&self, gauge: &Gauge) -> GaugeReading {
+        let (start_x, start_y, end_x, end_y) =
+            gauge.zone.bounds(self.image.width(), self.image.height());
+        let horizontal = matches!(
+            gauge.direction,
+            FillDirection::LeftToRight | FillDirection::RightToLeft
+        );
+        let (length, across) = if horizontal {
+            (end_x - start_x, end_y - start_y)
+        } else {
+            (end_y - start_y, end_x - start_x)
+        };
+        let slices: Vec<Slice> = (0..length)
+            .map(|i| {
+                let mut slice = Slice { fill: 0, empty: 0 };
+                for j in 0..across {
+                    let (x, y) = match gauge.direction {
+                        FillDirection::LeftToRight => (start_x + i, start_y + j),
+                        FillDirection::RightToLeft => (end_x - 1 - i, start_y + j),
+                        FillDirection::TopToBottom => (start_x + j, start_y + i),
+                        FillDirection::BottomToTop => (start_x + j, end_y - 1 - i),
+                    };
+                    let rgb = Rgb::from(self.image.get_pixel(x, y).0);
+                    if gauge.fill.matches_rgb(&rgb) {
+                        slice.fill += 1;
+                    } else if gauge.empty.matches_rgb(&rgb) {
+                        slice.empty += 1;
+                    }
+                }
+                slice
+            })
+            .collect();
+
+        let Some(first) = slices.iter().position(Slice::known) else {
+            return GaugeReading {
+                fill: 0.0,
+                confidence: 0.0,
+            };
+        };
+        let last = slices.iter().rposition(Slice::known).unwrap();
+        let bar = &slices[first..=last];
+
+        // Votes disagreeing with a boundary before slice `best`: empty slices before
+        // it plus filled slices from it on
+        let mut errors: usize = bar.iter().filter(|s| s.known() && s.filled()).count();
+        let (mut best, mut best_errors) = (0, errors);
+        for (index, slice) in bar.iter().enumerate() {
+            if slice.known() {
+                if slice.filled() {
+                    errors -= 1;
+                } else {
+                    errors += 1;
+                }
+            }
+            if errors < best_errors {
+                (best, best_errors) = (index + 1, errors);
+            }
+        }
+        // The first empty slice may be partly filled, anti-aliasing the boundary
+        let partial = bar
+            .get(best)
+            .filter(|slice| slice.known())
+            .map_or(0.0, |slice| {
+                slice.fill as f64 / (slice.fill + slice.empty) as f64
+            });
+
+        let known = bar.iter().filter(|slice| slice.known()).count();
+        let agreement = (known - best_errors) as f64 / known as f64;
+        let coverage = known as f64 / bar.len() as f64;
+        GaugeReading {
+            fill: ((best as f64 + partial) / bar.len() as f64).min(1.0),
+            confidence: agreement * coverage,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        data::{better_call_zone::Zone, point::Point},
+        image_analyzer::color::hsv::Hsv,
+        utils::scene::SceneBuilder,
+    };
+
+    fn gauge(zone: ImageZone, direction: FillDirection) -> Gauge {
+        Gauge {
+            zone,
+            direction,
+            fill: ColorDetection::hsv(Hsv::from([0.0, 1.0, 0.85]), Hsv::from([8.0, 0.1, 0.2, 0.0])),
+            empty: ColorDetection::rgb(Rgb::from([40, 40, 40]), Rgb::from([6, 6, 6, 0])),
+        }
+    }
+
+    #[test]
+    fn test_horizontal_bar_with_border_and_text() {
+        let mut builder = SceneBuilder::new(130, 30)
+            .background(Rgb::from([90, 90, 120]))
+            // 1 pixel border around a bar from x 10 to 110
+            .rect(
+                "border",
+                Zone::new(Point::new(9, 9), Point::new(111, 21)),
+                Rgb::from([200, 200, 200]),
+            )
+            .rect(
+                "empty",
+                Zone::new(Point::new(10, 10), Point::new(110, 20)),
+                Rgb::from([40, 40, 40]),
+            );
+        // Vertical gradient on the fill, darker at the bottom
+        for y in 10..20 {
+            let red = Rgb::from([255 - (y - 10) as u8 * 8, 0, 0]);
+            builder = builder.rect(
+                "fill",
+                Zone::new(Point::new(10, y), Point::new(70, y + 1)),
+                red,
+            );
+        }
+        let scene = builder
+            .text(
+                "text",
+                Point::new(40, 12),
+                "HP 60",
+                1,
+                Rgb::from([255, 255, 255]),
+            )
+            .render();
+        let analyzer = ImageAnalyzer::new(scene.image);
+
+        let zone = ImageZone::Partial(Point::new(5, 5), Point::new(115, 25));
+        let reading = analyzer.read_gauge(&gauge(zone.clone(), FillDirection::LeftToRight));
+        assert!((reading.fill - 0.6).abs() < 1e-9, "{:?}", reading);
+        assert_eq!(reading.confidence, 1.0);
+
+        // Read from the wrong side, 40 of the 100 slices contradict any boundary
+        let reading = analyzer.read_gauge(&gauge(zone, FillDirection::RightToLeft));
+        assert!((reading.confidence - 0.6).abs() < 1e-9, "{:?}", reading);
+    }
+
+    #[test]
+    fn test_vertical_and_noisy_bars() {
+        let red = Rgb::from([217, 0, 0]);
+        let scene = SceneBuilder::new(20, 100)
+            .background(Rgb::from([40, 40, 40]))
+            .rect(
+                "fill",
+                Zone::new(Point::new(5, 75), Point::new(15, 100)),
+                red,
+            )
+            // A stray fill colored slice above the boundary
+            .rect(
+                "glitch",
+                Zone::new(Point::new(5, 30), Point::new(15, 31)),
+                red,
+            )
+            // A slice that is neither fill nor empty
+            .rect(
+                "text",
+                Zone::new(Point::new(5, 50), Point::new(15, 51)),
+                Rgb::from([0, 0, 255]),
+            )
+            .render();
+        let analyzer = ImageAnalyzer::new(scene.image);
+        let zone = ImageZone::Partial(Point::new(5, 0), Point::new(15, 100));
+        let reading = analyzer.read_gauge(&gauge(zone.clone(), FillDirection::BottomToTop));
+        assert!((reading.fill - 0.25).abs() < 1e-9, "{:?}", reading);
+        assert!((reading.confidence - 0.98).abs() < 1e-9, "{:?}", reading);
+
+        let blank = ImageAnalyzer::new(image::ImageBuffer::new(20, 100));
+        assert_eq!(
+            blank.read_gauge(&gauge(zone, FillDirection::TopToBottom)),
+            GaugeReading {
+                fill: 0.0,
+                confidence: 0.0
+            }
+        );
+    }
+}
@@ -0,0 +1,335 @@
+use std::{fs, path::Path};
+
+use image::{ImageBuffer, Rgba};
+
+use crate::data::{better_call_zone::Zone, point::Point};
+
+use super::{detection::ColorDetection, mask::Mask, ImageAnalyzer, ImageZone};
+
+/// Ink of one character, cropped to its ink columns but keeping the full cell height
+/// so that glyphs sitting at different heights (`.`, `-`, `'`) stay apart
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlyphTemplate {
+    pub character: char,
+    pub width: u32,
+    pub height: u32,
+    ink: Vec<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecognizedChar {
+    pub character: char,
+    /// Share of the compared pixels agreeing with the template, from 0.0 to 1.0
+    pub score: f64,
+    /// Ink columns of the character over the whole zone height, end excluded
+    pub zone: Zone,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recognition {
+    pub text: String,
+    /// One entry per character of `text`, spaces included with a score of 1.0
+    pub characters: Vec<RecognizedChar>,
+}
+
+#[allow(dead_code)]
+impl Recognition {
+    /// Score of the least certain character, 0.0 for an empty text
+    pub fn min_score(&self) -> f64 {
+        self.characters
+            .iter()
+            .map(|character| character.score)
+            .reduce(f64::min)
+            .unwrap_or(0.0)
+    }
+}
+
+/// Templates of a fixed bitmap font
+#[derive(Debug, Clone, Default)]
+pub struct GlyphSet {
+    templates: Vec<GlyphTemplate>,
+    /// Gaps at least this wide between characters read as a space
+    space_width: Option<u32>,
+}
+
+/// Ink grid of a mask, row-major
+fn ink(mask: &Mask) -> Vec<bool> {
+    let mut ink = vec![false; (mask.width * mask.height) as usize];
+    for point in mask.points() {
+        let (x, y) = (point.x - mask.origin.x, point.y - mask.origin.y);
+        ink[(y * mask.width + x) as usize] = true;
+    }
+    ink
+}
+
+/// Runs of columns holding ink, as `start..end`
+fn ink_columns(ink: &[bool], width: u32, height: u32) -> Vec<(u32, u32)> {
+    let has_ink = |x: u32| (0..height).any(|y| ink[(y * width + x) as usize]);
+    let mut runs = Vec::new();
+    let mut start = None;
+    for x in 0..width {
+        match (has_ink(x), start) {
+            (true, None) => start = Some(x),
+            (false, Some(begin)) => {
+                runs.push((begin, x));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(begin) = start {
+        runs.push((begin, width));
+    }
+    runs
+}
+
+#[allow(dead_code)]
+impl GlyphSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_space_width(mut self, width: u32) -> Self {
+        self.space_width = Some(width);
+        self
+    }
+
+    pub fn templates(&self) -> &[GlyphTemplate] {
+        &self.templates
+    }
+
+    /// Registers `character` from an image of one glyph cell, ink being the pixels
+    /// matching `detection`
+    pub fn add_image(
+        &mut self,
+        character: char,
+        image: &ImageBuffer<Rgba<u8>, Vec<u8>>,
+        detection: &ColorDetection,
+    ) -> Result<(), String> {
+        let mask = ImageAnalyzer::new(image.clone()).detect_mask(ImageZone::Full, detection);
+        self.add_mask(character, &mask)
+    }
+
+    pub fn add_mask(&mut self, character: char, mask: &Mask) -> Result<(), String> {
+        let cell = ink(mask);
+        let (start, end) = match ink_columns(&cell, mask.width, mask.height)[..] {
+            [] => return Err(format!("No ink in the template of {:?}", character)),
+            [first, .., last] => (first.0, last.1),
+            [only] => only,
+        };
+        let width = end - start;
+        let ink = (0..mask.height)
+            .flat_map(|y| (start..end).map(move |x| (x, y)))
+            .map(|(x, y)| cell[(y * mask.width + x) as usize])
+            .collect();
+        self.templates
+            .retain(|template| template.character != character);
+        self.templates.push(GlyphTemplate {
+            character,
+            width,
+            height: mask.height,
+            ink,
+        });
+        Ok(())
+    }
+
+    /// Registers every image of `directory` named after its character, `7.png` for 7
+    pub fn load_dir<P: AsRef<Path>>(
+        &mut self,
+        directory: P,
+        detection: &ColorDetection,
+    ) -> Result<(), String> {
+        let directory = directory.as_ref();
+        let entries = fs::read_dir(directory)
+            .map_err(|error| format!("Cannot read {}: {}", directory.display(), error))?;
+        for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+            let stem = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let mut chars = stem.chars();
+            let (Some(character), None) = (chars.next(), chars.next()) else {
+                return Err(format!(
+                    "{} is not named after one character",
+                    path.display()
+                ));
+            };
+            let image = image::open(&path)
+                .map_err(|error| format!("Cannot read {}: {}", path.display(), error))?
+                .to_rgba8();
+            self.add_image(character, &image, detection)?;
+        }
+        Ok(())
+    }
+
+    /// Binarizes `zone` with `detection`, splits it into characters at empty columns
+    /// and matches each against every template at every height the zone allows.
+    /// Touching characters are read as one and get a low score.
+    pub fn recognize(
+        &self,
+        analyzer: &ImageAnalyzer,
+        zone: ImageZone,
+        detection: &ColorDetection,
+    ) -> Recognition {
+        let mask = analyzer.detect_mask(zone, detection);
+        let grid = ink(&mask);
+        let mut recognition = Recognition {
+            text: String::new(),
+            characters: Vec::new(),
+        };
+        let mut previous_end = None;
+        for (start, end) in ink_columns(&grid, mask.width, mask.height) {
+            let zone_of = |start: u32, end: u32| {
+                Zone::new(
+                    Point::new(mask.origin.x + start, mask.origin.y),
+                    Point::new(mask.origin.x + end, mask.origin.y + mask.height),
+                )
+            };
+            if let (Some(space), Some(previous)) = (self.space_width, previous_end) {
+                if start - previous >= space {
+                    recognition.text.push(' ');
+                    recognition.characters.push(RecognizedChar {
+                        character: ' ',
+                        score: 1.0,
+                        zone: zone_of(previous, start),
+                    });
+                }
+            }
+            previous_end = Some(end);
+
+            let (character, score) = self
+                .templates
+                .iter()
+                .map(|template| {
+                    (
+                        template.character,
+                        self.score(template, &grid, &mask, start, end),
+                    )
+                })
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+                .unwrap_or(('?', 0.0));
+            recognition.text.push(character);
+            recognition.characters.push(RecognizedChar {
+                character,
+                score,
+                zone: zone_of(start, end),
+            });
+        }
+        recognition
+    }
+
+    /// Best agreement of `template` with columns `start..end` over the vertical
+    /// offsets where it fits, ink outside of the template rows counting against it
+    fn score(
+        &self,
+        template: &GlyphTemplate,
+        grid: &[bool],
+        mask: &Mask,
+        start: u32,
+        end: u32,
+    ) -> f64 {
+        if template.height > mask.height {
+            return 0.0;
+        }
+        let width = (end - start).max(template.width);
+        let at = |x: u32, y: u32| x < end - start && grid[(y * mask.width + start + x) as usize];
+        let total_ink = (0..mask.height)
+            .flat_map(|y| (0..end - start).map(move |x| (x, y)))
+            .filter(|(x, y)| at(*x, *y))
+            .count();
+        (0..=mask.height - template.height)
+            .map(|offset| {
+                let mut agree = 0;
+                let mut inside_ink = 0;
+                for y in 0..template.height {
+                    for x in 0..width {
+                        let ink = at(x, offset + y);
+                        inside_ink += ink as usize;
+                        let expected =
+                            x < template.width && template.ink[(y * template.width + x) as usize];
+                        agree += (ink == expected) as usize;
+                    }
+                }
+                let outside_ink = total_ink - inside_ink;
+                agree as f64 / ((width * template.height) as usize + outside_ink) as f64
+            })
+            .fold(0.0, f64::max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        image_analyzer::color::rgb::Rgb,
+        utils::scene::{Orientation, SceneBuilder},
+    };
+
+    fn white() -> ColorDetection {
+        ColorDetection::rgb(Rgb::from([255, 255, 255]), Rgb::from([30, 30, 30, 0]))
+    }
+
+    fn digits() -> GlyphSet {
+        let mut glyphs = GlyphSet::new().with_space_width(5);
+        for character in "0123456789.-".chars() {
+            // One glyph cell of the font at scale 2
+            let cell = SceneBuilder::new(6, 10)
+                .text(
+                    "",
+                    Point::new(0, 0),
+                    &character.to_string(),
+                    2,
+                    Rgb::from([250, 250, 250]),
+                )
+                .render();
+            glyphs.add_image(character, &cell.image, &white()).unwrap();
+        }
+        glyphs
+    }
+
+    #[test]
+    fn test_read_numbers() {
+        let glyphs = digits();
+        assert_eq!(glyphs.templates().len(), 12);
+        let scene = SceneBuilder::new(120, 30)
+            .gradient(
+                Rgb::from([10, 20, 60]),
+                Rgb::from([60, 20, 10]),
+                Orientation::Horizontal,
+            )
+            .noise(10)
+            .text(
+                "level",
+                Point::new(8, 10),
+                "1.5 -2 9087",
+                2,
+                Rgb::from([240, 245, 250]),
+            )
+            .render();
+        let analyzer = ImageAnalyzer::new(scene.image);
+        let zone = ImageZone::Partial(Point::new(4, 6), Point::new(116, 24));
+        let recognition = glyphs.recognize(&analyzer, zone, &white());
+        assert_eq!(recognition.text, "1.5 -2 9087");
+        assert_eq!(recognition.min_score(), 1.0);
+        assert_eq!(recognition.characters[0].zone.start, Point::new(8, 6));
+        assert_eq!(recognition.characters[0].zone.end, Point::new(14, 24));
+    }
+
+    #[test]
+    fn test_unknown_glyph_scores_low() {
+        let glyphs = digits();
+        let scene = SceneBuilder::new(40, 20)
+            .text("", Point::new(2, 2), "7W", 2, Rgb::from([255, 255, 255]))
+            .render();
+        let analyzer = ImageAnalyzer::new(scene.image);
+        let recognition = glyphs.recognize(&analyzer, ImageZone::Full, &white());
+        assert_eq!(recognition.characters.len(), 2);
+        assert_eq!(recognition.characters[0].character, '7');
+        assert_eq!(recognition.characters[0].score, 1.0);
+        assert!(recognition.characters[1].score < 0.9, "{:?}", recognition);
+
+        let mut empty = GlyphSet::new();
+        let blank = ImageBuffer::new(6, 10);
+        assert!(empty.add_image('x', &blank, &white()).is_err());
+    }
+}
@@ -0,0 +1,149 @@
+use std::time::Duration;
+
+use crate::data::point::Point;
+
+/// Position and velocity along one axis with their covariance
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Axis {
+    position: f64,
+    velocity: f64,
+    /// Covariance `[[position, both], [both, velocity]]`
+    p00: f64,
+    p01: f64,
+    p11: f64,
+}
+
+impl Axis {
+    fn new(position: f64, measurement_noise: f64) -> Self {
+        Self {
+            position,
+            velocity: 0.0,
+            p00: measurement_noise,
+            p01: 0.0,
+            // Nothing is known about the velocity yet
+            p11: 1e6,
+        }
+    }
+
+    /// Moves the state `dt` seconds ahead, uncertainty growing with random
+    /// accelerations of variance `process_noise`
+    fn predict(&mut self, dt: f64, process_noise: f64) {
+        self.position += self.velocity * dt;
+        let (dt2, dt3, dt4) = (dt * dt, dt * dt * dt, dt * dt * dt * dt);
+        self.p00 += 2.0 * dt * self.p01 + dt2 * self.p11 + dt4 / 4.0 * process_noise;
+        self.p01 += dt * self.p11 + dt3 / 2.0 * process_noise;
+        self.p11 += dt2 * process_noise;
+    }
+
+    fn correct(&mut self, measured: f64, measurement_noise: f64) {
+        let innovation = measured - self.position;
+        let s = self.p00 + measurement_noise;
+        let (k0, k1) = (self.p00 / s, self.p01 / s);
+        self.position += k0 * innovation;
+        self.velocity += k1 * innovation;
+        self.p11 -= k1 * self.p01;
+        self.p01 *= 1.0 - k0;
+        self.p00 *= 1.0 - k0;
+    }
+}
+
+/// Constant-velocity Kalman filter on a moving point, x and y filtered independently
+#[derive(Debug, Clone, PartialEq)]
+pub struct KalmanFilter {
+    x: Axis,
+    y: Axis,
+    /// Variance of the accelerations, in pixels²/s⁴
+    process_noise: f64,
+    /// Variance of the measured positions, in pixels²
+    measurement_noise: f64,
+    timestamp: Duration,
+}
+
+#[allow(dead_code)]
+impl KalmanFilter {
+    pub fn new(
+        initial: &Point,
+        timestamp: Duration,
+        process_noise: f64,
+        measurement_noise: f64,
+    ) -> Self {
+        Self {
+            x: Axis::new(initial.x as f64, measurement_noise),
+            y: Axis::new(initial.y as f64, measurement_noise),
+            process_noise,
+            measurement_noise,
+            timestamp,
+        }
+    }
+
+    /// Folds in a position measured at `timestamp`, later than the previous one
+    pub fn update(&mut self, measured: &Point, timestamp: Duration) {
+        let dt = timestamp.saturating_sub(self.timestamp).as_secs_f64();
+        for (axis, value) in [(&mut self.x, measured.x), (&mut self.y, measured.y)] {
+            axis.predict(dt, self.process_noise);
+            axis.correct(value as f64, self.measurement_noise);
+        }
+        self.timestamp = self.timestamp.max(timestamp);
+    }
+
+    /// Smoothed position at the last update
+    pub fn position(&self) -> (f64, f64) {
+        (self.x.position, self.y.position)
+    }
+
+    /// Pixels per second along x and y
+    pub fn velocity(&self) -> (f64, f64) {
+        (self.x.velocity, self.y.velocity)
+    }
+
+    /// Expected position `ahead` of the last update, clamped to the image origin
+    pub fn predict(&self, ahead: Duration) -> Point {
+        let dt = ahead.as_secs_f64();
+        let at = |axis: &Axis| (axis.position + axis.velocity * dt).round().max(0.0) as u32;
+        Point::new(at(&self.x), at(&self.y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_smooths_and_leads_a_moving_target() {
+        // 100 px/s to the right, 20 px/s up, measured every 50 ms with up to 3 px of error
+        let truth = |t: f64| (20.0 + 100.0 * t, 300.0 - 20.0 * t);
+        let noise = [3.0, -2.0, 0.0, -3.0, 2.0, 1.0, -1.0];
+        let measure = |i: usize| {
+            let (x, y) = truth(i as f64 * 0.05);
+            let error = noise[i % noise.len()];
+            Point::new((x + error).round() as u32, (y - error).round() as u32)
+        };
+        let mut filter = KalmanFilter::new(&measure(0), Duration::ZERO, 400.0, 4.0);
+        let (mut raw_error, mut smoothed_error) = (0.0, 0.0);
+        for i in 1..40 {
+            filter.update(&measure(i), Duration::from_millis(i as u64 * 50));
+            if i >= 20 {
+                let (x, _) = truth(i as f64 * 0.05);
+                raw_error += (measure(i).x as f64 - x).abs();
+                smoothed_error += (filter.position().0 - x).abs();
+            }
+        }
+        assert!(
+            smoothed_error < raw_error / 1.5,
+            "{smoothed_error} {raw_error}"
+        );
+        let (vx, vy) = filter.velocity();
+        assert!(
+            (vx - 100.0).abs() < 10.0 && (vy + 20.0).abs() < 10.0,
+            "{vx} {vy}"
+        );
+
+        // 300 ms after the last measurement at 1.95 s
+        let (x, y) = truth(2.25);
+        let predicted = filter.predict(Duration::from_millis(300));
+        assert!(
+            predicted.distance(&Point::new(x as u32, y as u32)) < 5.0,
+            "{predicted:?}"
+        );
+    }
+}
@@ -0,0 +1,271 @@
+use std::collections::BTreeMap;
+
+use image::{ImageBuffer, Rgba};
+
+use crate::data::point::Point;
+
+use super::{blob::extract_blobs, detection::ColorDetection, ImageAnalyzer, ImageZone};
+
+/// Rectangle placed relative to an anchor, it may start left of or above it
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelativeZone {
+    pub offset: (i32, i32),
+    pub size: (u32, u32),
+}
+
+/// How the origin of a layout is found on a frame
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Anchor {
+    /// Fixed offset from the parent anchor, from the image origin for a root layout
+    Offset(i32, i32),
+    /// Top left corner of the best match of `template`, rejected above `max_error`
+    /// (see `TemplateMatch::error`)
+    Template {
+        template: ImageBuffer<Rgba<u8>, Vec<u8>>,
+        search: Option<RelativeZone>,
+        max_error: f64,
+    },
+    /// Top left corner of the largest blob of pixels matching `detection`
+    Color {
+        detection: ColorDetection,
+        search: Option<RelativeZone>,
+        min_size: usize,
+    },
+}
+
+/// Named zones that move together with their anchor. `search` zones of anchors and
+/// child layouts are relative to the parent anchor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layout {
+    pub name: String,
+    pub anchor: Anchor,
+    pub zones: Vec<(String, RelativeZone)>,
+    pub children: Vec<Layout>,
+}
+
+/// Absolute zones of a layout for one frame, named `layout.child.zone`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ResolvedLayout {
+    /// Anchors found inside of the image, offsets may place one outside
+    pub anchors: BTreeMap<String, Point>,
+    pub zones: BTreeMap<String, ImageZone>,
+}
+
+#[allow(dead_code)]
+impl RelativeZone {
+    pub fn new(x: i32, y: i32, width: u32, height: u32) -> Self {
+        Self {
+            offset: (x, y),
+            size: (width, height),
+        }
+    }
+
+    /// Absolute zone from `origin`, clipped to the image, `None` when nothing is left
+    pub fn resolve(&self, origin: (i64, i64), width: u32, height: u32) -> Option<ImageZone> {
+        let start_x = origin.0 + self.offset.0 as i64;
+        let start_y = origin.1 + self.offset.1 as i64;
+        let clip = |value: i64, max: u32| value.clamp(0, max as i64) as u32;
+        let start = Point::new(clip(start_x, width), clip(start_y, height));
+        let end = Point::new(
+            clip(start_x + self.size.0 as i64, width),
+            clip(start_y + self.size.1 as i64, height),
+        );
+        (start.x < end.x && start.y < end.y).then_some(ImageZone::Partial(start, end))
+    }
+}
+
+#[allow(dead_code)]
+impl Layout {
+    pub fn new(name: &str, anchor: Anchor) -> Self {
+        Self {
+            name: name.to_string(),
+            anchor,
+            zones: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    pub fn zone(mut self, name: &str, zone: RelativeZone) -> Self {
+        self.zones.push((name.to_string(), zone));
+        self
+    }
+
+    pub fn child(mut self, layout: Layout) -> Self {
+        self.children.push(layout);
+        self
+    }
+
+    /// Finds every anchor on the analyzed frame and places the zones. Fails when an
+    /// anchor is missing or a zone falls outside of the image.
+    pub fn resolve(&self, analyzer: &ImageAnalyzer) -> Result<ResolvedLayout, String> {
+        let mut resolved = ResolvedLayout::default();
+        self.resolve_into(analyzer, (0, 0), "", &mut resolved)?;
+        Ok(resolved)
+    }
+
+    fn resolve_into(
+        &self,
+        analyzer: &ImageAnalyzer,
+        parent: (i64, i64),
+        prefix: &str,
+        resolved: &mut ResolvedLayout,
+    ) -> Result<(), String> {
+        let path = format!("{}{}", prefix, self.name);
+        let (width, height) = analyzer.image.dimensions();
+        let search_zone = |search: &Option<RelativeZone>| match search {
+            Some(search) => search
+                .resolve(parent, width, height)
+                .ok_or(format!("Search zone of {} is outside of the image", path)),
+            None => Ok(ImageZone::Full),
+        };
+        let origin = match &self.anchor {
+            Anchor::Offset(x, y) => Some((parent.0 + *x as i64, parent.1 + *y as i64)),
+            Anchor::Template {
+                template,
+                search,
+                max_error,
+            } => analyzer
+                .match_template(template, search_zone(search)?)
+                .filter(|found| found.error <= *max_error)
+                .map(|found| (found.position.x as i64, found.position.y as i64)),
+            Anchor::Color {
+                detection,
+                search,
+                min_size,
+            } => {
+                let points = analyzer.detect(search_zone(search)?, detection).points();
+                extract_blobs(&points, *min_size)
+                    .into_iter()
+                    .max_by_key(|blob| blob.size())
+                    .map(|blob| (blob.zone.start.x as i64, blob.zone.start.y as i64))
+            }
+        };
+        let origin = origin.ok_or(format!("Anchor of {} not found", path))?;
+        if origin.0 >= 0 && origin.1 >= 0 {
+            resolved
+                .anchors
+                .insert(path.clone(), Point::new(origin.0 as u32, origin.1 as u32));
+        }
+
+        for (name, zone) in &self.zones {
+            let zone = zone
+                .resolve(origin, width, height)
+                .ok_or(format!("{}.{} is outside of the image", path, name))?;
+            resolved.zones.insert(format!("{}.{}", path, name), zone);
+        }
+        let prefix = format!("{}.", path);
+        for child in &self.children {
+            child.resolve_into(analyzer, origin, &prefix, resolved)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::imageops;
+
+    use super::*;
+    use crate::{
+        data::better_call_zone::Zone, image_analyzer::color::rgb::Rgb, utils::scene::SceneBuilder,
+    };
+
+    const MARKER: Rgb = Rgb {
+        r: 0,
+        g: 220,
+        b: 90,
+        a: 255,
+    };
+
+    /// An inventory panel at `(x, y)` with a bag marker inside, `bag` pixels right of
+    /// the panel corner
+    fn frame(x: u32, y: u32, bag: u32) -> ImageBuffer<Rgba<u8>, Vec<u8>> {
+        SceneBuilder::new(200, 150)
+            .background(Rgb::from([25, 25, 35]))
+            .noise(4)
+            .rect(
+                "panel",
+                Zone::new(Point::new(x, y), Point::new(x + 60, y + 50)),
+                Rgb::from([90, 70, 50]),
+            )
+            .text(
+                "title",
+                Point::new(x + 2, y + 2),
+                "INV",
+                1,
+                Rgb::from([240, 240, 200]),
+            )
+            .rect(
+                "bag",
+                Zone::new(Point::new(x + bag, y + 30), Point::new(x + bag + 4, y + 34)),
+                MARKER,
+            )
+            .render()
+            .image
+    }
+
+    fn layout() -> Layout {
+        let title = imageops::crop_imm(&frame(0, 0, 30), 0, 0, 16, 9).to_image();
+        Layout::new(
+            "inventory",
+            Anchor::Template {
+                template: title,
+                search: None,
+                max_error: 2.0,
+            },
+        )
+        .zone("slot_0", RelativeZone::new(4, 12, 10, 10))
+        .zone("slot_3", RelativeZone::new(40, 12, 10, 10))
+        .child(
+            Layout::new(
+                "bag",
+                Anchor::Color {
+                    detection: ColorDetection::rgb(MARKER, Rgb::from([0, 0, 0, 0])),
+                    search: Some(RelativeZone::new(0, 25, 60, 25)),
+                    min_size: 4,
+                },
+            )
+            .zone("count", RelativeZone::new(-2, -8, 8, 6)),
+        )
+    }
+
+    #[test]
+    fn test_zones_follow_their_anchors() {
+        let layout = layout();
+        let at = |x: u32, y: u32, w: u32, h: u32| {
+            ImageZone::Partial(Point::new(x, y), Point::new(x + w, y + h))
+        };
+        let resolved = layout
+            .resolve(&ImageAnalyzer::new(frame(20, 30, 30)))
+            .unwrap();
+        assert_eq!(resolved.anchors["inventory"], Point::new(20, 30));
+        assert_eq!(resolved.zones["inventory.slot_3"], at(60, 42, 10, 10));
+        assert_eq!(resolved.zones["inventory.bag.count"], at(48, 52, 8, 6));
+
+        // Panel dragged and bag moved inside it
+        let resolved = layout
+            .resolve(&ImageAnalyzer::new(frame(130, 90, 10)))
+            .unwrap();
+        assert_eq!(resolved.zones["inventory.slot_0"], at(134, 102, 10, 10));
+        assert_eq!(resolved.anchors["inventory.bag"], Point::new(140, 120));
+        assert_eq!(resolved.zones["inventory.bag.count"], at(138, 112, 8, 6));
+    }
+
+    #[test]
+    fn test_missing_anchor_and_outside_zone() {
+        let layout = layout();
+        let empty = SceneBuilder::new(200, 150).render().image;
+        assert_eq!(
+            layout.resolve(&ImageAnalyzer::new(empty)),
+            Err("Anchor of inventory not found".to_string())
+        );
+        let clipped = Layout::new("hud", Anchor::Offset(190, 140))
+            .zone("corner", RelativeZone::new(0, 0, 20, 20))
+            .zone("beyond", RelativeZone::new(15, 0, 5, 5));
+        assert_eq!(
+            clipped.resolve(&ImageAnalyzer::new(frame(0, 0, 30))),
+            Err("hud.beyond is outside of the image".to_string())
+        );
+    }
+}
@@ -0,0 +1,206 @@
+use rayon::prelude::*;
+
+use crate::data::point::Point;
+
+use super::{
+    color::{hsv::Hsv, rgb::Rgb, Color},
+    detection::ColorDetection,
+    pixel::PixelVec,
+    ImageAnalyzer, ImageZone,
+};
+
+/// One bit for each of the 16.7M opaque RGB values (2 MiB)
+pub struct RgbSet {
+    bits: Vec<u64>,
+}
+#[allow(dead_code)]
+impl RgbSet {
+    pub fn from_fn<F>(contains: F) -> Self
+    where
+        F: Fn(Rgb) -> bool + Sync,
+    {
+        let bits = (0..1usize << 18)
+            .into_par_iter()
+            .map(|word| {
+                let mut bits = 0;
+                for bit in 0..64 {
+                    let index = word * 64 + bit;
+                    let rgb = Rgb::from([(index >> 16) as u8, (index >> 8) as u8, index as u8]);
+                    if contains(rgb) {
+                        bits |= 1 << bit;
+                    }
+                }
+                bits
+            })
+            .collect();
+        Self { bits }
+    }
+
+    #[inline]
+    pub fn contains(&self, r: u8, g: u8, b: u8) -> bool {
+        let index = (r as usize) << 16 | (g as usize) << 8 | b as usize;
+        self.bits[index / 64] >> (index % 64) & 1 == 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.bits
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.iter().all(|word| *word == 0)
+    }
+}
+
+/// A `ColorDetection` turned into table lookups: one bit per RGB value and one per
+/// alpha value, so testing a pixel costs two loads instead of an HSV conversion.
+/// `ColorDetection::Any` keeps a pair of tables per alternative.
+pub struct CompiledDetection {
+    tables: Vec<(RgbSet, [u64; 4])>,
+}
+#[allow(dead_code)]
+impl CompiledDetection {
+    #[inline]
+    pub fn matches_rgb(&self, rgb: &Rgb) -> bool {
+        self.tables.iter().any(|(set, alpha)| {
+            alpha[rgb.a as usize / 64] >> (rgb.a % 64) & 1 == 1 && set.contains(rgb.r, rgb.g, rgb.b)
+        })
+    }
+
+    pub fn matches(&self, color: &Color) -> bool {
+        self.matches_rgb(&color.get_rgb())
+    }
+}
+
+#[allow(dead_code)]
+impl ColorDetection {
+    /// Precomputes the detection for every RGB value. Alpha is compared on its own
+    /// for both tolerance modes, which is what lets it live in a separate table.
+    pub fn compile(&self) -> CompiledDetection {
+        let tables = self
+            .alternatives()
+            .into_iter()
+            .map(|detection| (compile_rgb(detection), compile_alpha(detection)))
+            .collect();
+        CompiledDetection { tables }
+    }
+}
+
+fn compile_rgb(detection: &ColorDetection) -> RgbSet {
+    let opaque = match detection {
+        ColorDetection::Rgb { color, tolerance } => ColorDetection::Rgb {
+            color: Rgb { a: 255, ..*color },
+            tolerance: Rgb {
+                a: 255,
+                ..*tolerance
+            },
+        },
+        ColorDetection::Hsv { color, tolerance } => ColorDetection::Hsv {
+            color: Hsv { a: 1.0, ..*color },
+            tolerance: Hsv {
+                a: f64::INFINITY,
+                ..*tolerance
+            },
+        },
+        ColorDetection::Any(_) => unreachable!("alternatives are single detections"),
+    };
+    RgbSet::from_fn(|rgb| opaque.matches_rgb(&rgb))
+}
+
+fn compile_alpha(detection: &ColorDetection) -> [u64; 4] {
+    let mut alpha = [0; 4];
+    for a in 0..=255u8 {
+        let matches = match detection {
+            ColorDetection::Rgb { color, tolerance } => color.a.abs_diff(a) <= tolerance.a,
+            ColorDetection::Hsv { color, tolerance } => {
+                (color.a - a as f64 / 255.0).abs() <= tolerance.a
+            }
+            ColorDetection::Any(_) => unreachable!("alternatives are single detections"),
+        };
+        if matches {
+            alpha[a as usize / 64] |= 1 << (a % 64);
+        }
+    }
+    alpha
+}
+
+#[allow(dead_code)]
+impl ImageAnalyzer {
+    /// Same pixels as [`ImageAnalyzer::detect`] with the detection the table was compiled from
+    pub fn detect_compiled(&self, zone: ImageZone, detection: &CompiledDetection) -> PixelVec {
+        let (start_x, start_y, end_x, end_y) = zone.bounds(self.image.width(), self.image.height());
+        let mut points = PixelVec::new();
+        for y in start_y..end_y {
+            for x in start_x..end_x {
+                let rgb = Rgb::from(self.image.get_pixel(x, y).0);
+                if detection.matches_rgb(&rgb) {
+                    points.push((Color::Rgb(rgb), Point { x, y }));
+                }
+            }
+        }
+        points
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{ImageBuffer, Rgba};
+
+    use super::*;
+
+    #[test]
+    fn test_compiled_hsv_detection() {
+        let detection = ColorDetection::hsv(
+            Hsv::from([200.0, 0.6, 0.7, 1.0]),
+            Hsv::from([15.0, 0.2, 0.25, 0.1]),
+        );
+        let compiled = detection.compile();
+        assert!(!compiled.tables[0].0.is_empty());
+        for r in (0..=255).step_by(3) {
+            for g in (0..=255).step_by(5) {
+                for b in (0..=255).step_by(7) {
+                    for a in [0, 200, 240, 255] {
+                        let rgb = Rgb::from([r, g, b, a]);
+                        assert_eq!(
+                            compiled.matches_rgb(&rgb),
+                            detection.matches_rgb(&rgb),
+                            "{:?}",
+                            rgb
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_compiled_rgb_detection() {
+        let detection = ColorDetection::rgb(Rgb::from([10, 200, 30, 128]), Rgb::from([4, 5, 6, 7]));
+        let compiled = detection.compile();
+        assert_eq!(compiled.tables[0].0.len(), 9 * 11 * 13);
+        assert!(compiled.matches_rgb(&Rgb::from([14, 195, 36, 135])));
+        assert!(!compiled.matches_rgb(&Rgb::from([14, 195, 36, 136])));
+        assert!(!compiled.matches_rgb(&Rgb::from([15, 195, 36, 128])));
+    }
+
+    #[test]
+    fn test_detect_compiled_matches_detect() {
+        let image = ImageBuffer::from_fn(64, 48, |x, y| {
+            Rgba([(x * 4) as u8, (y * 5) as u8, 180, 255])
+        });
+        let analyzer = ImageAnalyzer::new(image);
+        let detection = ColorDetection::hsv(
+            Hsv::from([230.0, 0.5, 0.7]),
+            Hsv::from([20.0, 0.3, 0.2, 0.0]),
+        );
+        let expected = analyzer.detect(ImageZone::Full, &detection);
+        let actual = analyzer.detect_compiled(ImageZone::Full, &detection.compile());
+        assert!(expected.points_count() > 0);
+        assert_eq!(expected.points_count(), actual.points_count());
+        for (expected, actual) in expected.pixels().iter().zip(actual.pixels().iter()) {
+            assert_eq!(expected.points, actual.points);
+        }
+    }
+}
@@ -0,0 +1,392 @@
+use image::{GrayImage, ImageBuffer, Luma, Rgba};
+
+use crate::data::point::Point;
+
+use super::{
+    blob::{extract_blobs, Blob},
+    color::{rgb::Rgb, Color},
+    detection::ColorDetection,
+    pixel::PixelVec,
+    simd::RgbaMatcher,
+    ImageAnalyzer, ImageZone,
+};
+
+/// Detected pixels of a zone packed one bit per pixel, bit `x % 64` of word `x / 64`
+/// of a row holds the pixel `origin.x + x`. Positions are image coordinates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mask {
+    pub origin: Point,
+    pub width: u32,
+    pub height: u32,
+    words: usize,
+    bits: Vec<u64>,
+}
+
+#[allow(dead_code)]
+impl Mask {
+    pub fn new(origin: Point, width: u32, height: u32) -> Self {
+        let words = (width as usize).div_ceil(64);
+        Self {
+            origin,
+            width,
+            height,
+            words,
+            bits: vec![0; words * height as usize],
+        }
+    }
+
+    /// Empty mask covering `zone` of an image of the given size
+    pub fn for_zone(zone: &ImageZone, width: u32, height: u32) -> Self {
+        let (start_x, start_y, end_x, end_y) = zone.bounds(width, height);
+        Self::new(
+            Point::new(start_x, start_y),
+            end_x - start_x,
+            end_y - start_y,
+        )
+    }
+
+    /// Mask built from rows packed like [`ImageAnalyzer::simd_detect`] returns them
+    pub fn from_rows(origin: Point, width: u32, rows: Vec<Vec<u64>>) -> Self {
+        let mut mask = Self::new(origin, width, rows.len() as u32);
+        for (y, row) in rows.iter().enumerate() {
+            mask.row_mut(y).copy_from_slice(row);
+        }
+        mask.clear_padding();
+        mask
+    }
+
+    /// Mask of the points of `pixels`, points outside of the covered zone are ignored
+    pub fn from_pixels(pixels: &PixelVec, origin: Point, width: u32, height: u32) -> Self {
+        let mut mask = Self::new(origin, width, height);
+        for pixel in pixels.pixels() {
+            for point in &pixel.points {
+                mask.set(point, true);
+            }
+        }
+        mask
+    }
+
+    /// Pixels of `image` at or above `threshold` are set
+    pub fn from_gray_image(image: &GrayImage, origin: Point, threshold: u8) -> Self {
+        let mut mask = Self::new(origin, image.width(), image.height());
+        for (x, y, value) in image.enumerate_pixels() {
+            if value.0[0] >= threshold {
+                mask.set_local(x, y);
+            }
+        }
+        mask
+    }
+
+    /// White where set, black elsewhere, the size of the mask
+    pub fn to_gray_image(&self) -> GrayImage {
+        ImageBuffer::from_fn(self.width, self.height, |x, y| {
+            Luma([if self.get_local(x, y) { 255 } else { 0 }])
+        })
+    }
+
+    /// Set points with their color in `image`
+    pub fn to_pixels(&self, image: &ImageBuffer<Rgba<u8>, Vec<u8>>) -> PixelVec {
+        let mut pixels = PixelVec::new();
+        for point in self.points() {
+            let rgb = Rgb::from(image.get_pixel(point.x, point.y).0);
+            pixels.push((Color::Rgb(rgb), point));
+        }
+        pixels
+    }
+
+    pub fn get(&self, point: &Point) -> bool {
+        match self.local(point) {
+            Some((x, y)) => self.get_local(x, y),
+            None => false,
+        }
+    }
+
+    /// Sets or clears a point, points outside of the covered zone are ignored
+    pub fn set(&mut self, point: &Point, value: bool) {
+        if let Some((x, y)) = self.local(point) {
+            let index = y as usize * self.words + x as usize / 64;
+            if value {
+                self.bits[index] |= 1 << (x % 64);
+            } else {
+                self.bits[index] &= !(1 << (x % 64));
+            }
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.bits
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.iter().all(|word| *word == 0)
+    }
+
+    /// Set points in row-major order
+    pub fn points(&self) -> Vec<Point> {
+        let mut points = Vec::with_capacity(self.count());
+        for y in 0..self.height as usize {
+            for (word_index, word) in self.row(y).iter().enumerate() {
+                let mut word = *word;
+                while word != 0 {
+                    let bit = word.trailing_zeros();
+                    word &= word - 1;
+                    points.push(Point::new(
+                        self.origin.x + word_index as u32 * 64 + bit,
+                        self.origin.y + y as u32,
+                    ));
+                }
+            }
+        }
+        points
+    }
+
+    pub fn blobs(&self, min_size: usize) -> Vec<Blob> {
+        extract_blobs(&self.points(), min_size)
+    }
+
+    /// Keeps the points whose whole `(2 * radius + 1)` square neighbourhood is set,
+    /// outside of the mask counts as unset
+    pub fn erode(&self, radius: u32) -> Mask {
+        let mut mask = self.clone();
+        for _ in 0..radius {
+            mask = mask.step(false);
+        }
+        mask
+    }
+
+    /// Sets the points with at least one set point in their `(2 * radius + 1)` square
+    /// neighbourhood, growing stops at the mask border
+    pub fn dilate(&self, radius: u32) -> Mask {
+        let mut mask = self.clone();
+        for _ in 0..radius {
+            mask = mask.step(true);
+        }
+        mask
+    }
+
+    /// Erosion then dilation, removes specks smaller than the structuring square
+    pub fn open(&self, radius: u32) -> Mask {
+        self.erode(radius).dilate(radius)
+    }
+
+    /// Dilation then erosion, fills holes smaller than the structuring square
+    pub fn close(&self, radius: u32) -> Mask {
+        self.dilate(radius).erode(radius)
+    }
+
+    pub fn and(&self, other: &Mask) -> Result<Mask, String> {
+        self.combine(other, |a, b| a & b)
+    }
+
+    pub fn or(&self, other: &Mask) -> Result<Mask, String> {
+        self.combine(other, |a, b| a | b)
+    }
+
+    pub fn xor(&self, other: &Mask) -> Result<Mask, String> {
+        self.combine(other, |a, b| a ^ b)
+    }
+
+    fn combine(&self, other: &Mask, op: impl Fn(u64, u64) -> u64) -> Result<Mask, String> {
+        if self.origin != other.origin || self.width != other.width || self.height != other.height {
+            return Err("Masks do not cover the same zone".to_string());
+        }
+        let mut mask = self.clone();
+        for (word, other) in mask.bits.iter_mut().zip(&other.bits) {
+            *word = op(*word, *other);
+        }
+        Ok(mask)
+    }
+
+    /// One 3x3 dilation (`grow`) or erosion, separable into a horizontal then a
+    /// vertical pass on whole words
+    fn step(&self, grow: bool) -> Mask {
+        let combine = |a: u64, b: u64| if grow { a | b } else { a & b };
+        // Padding bits and missing neighbour words read as unset, the outside of the mask
+        let mut horizontal = self.clone();
+        for y in 0..self.height as usize {
+            let row = self.row(y);
+            let out = horizontal.row_mut(y);
+            for index in 0..row.len() {
+                let before = if index > 0 { row[index - 1] >> 63 } else { 0 };
+                let after = if index + 1 < row.len() {
+                    row[index + 1] << 63
+                } else {
+                    0
+                };
+                let left = (row[index] << 1) | before;
+                let right = (row[index] >> 1) | after;
+                out[index] = combine(row[index], combine(left, right));
+            }
+        }
+        horizontal.clear_padding();
+
+        let mut mask = horizontal.clone();
+        for y in 0..self.height as usize {
+            for index in 0..self.words {
+                let word = horizontal.row(y)[index];
+                let above = if y > 0 {
+                    horizontal.row(y - 1)[index]
+                } else {
+                    0
+                };
+                let below = if y + 1 < self.height as usize {
+                    horizontal.row(y + 1)[index]
+                } else {
+                    0
+                };
+                mask.row_mut(y)[index] = combine(word, combine(above, below));
+            }
+        }
+        mask
+    }
+
+    fn local(&self, point: &Point) -> Option<(u32, u32)> {
+        let x = point.x.checked_sub(self.origin.x)?;
+        let y = point.y.checked_sub(self.origin.y)?;
+        (x < self.width && y < self.height).then_some((x, y))
+    }
+
+    fn get_local(&self, x: u32, y: u32) -> bool {
+        self.bits[y as usize * self.words + x as usize / 64] & (1 << (x % 64)) != 0
+    }
+
+    fn set_local(&mut self, x: u32, y: u32) {
+        self.bits[y as usize * self.words + x as usize / 64] |= 1 << (x % 64);
+    }
+
+    fn row(&self, y: usize) -> &[u64] {
+        &self.bits[y * self.words..(y + 1) * self.words]
+    }
+
+    fn row_mut(&mut self, y: usize) -> &mut [u64] {
+        &mut self.bits[y * self.words..(y + 1) * self.words]
+    }
+
+    /// Clears the bits past `width` in the last word of every row
+    fn clear_padding(&mut self) {
+        let used = self.width % 64;
+        if used == 0 || self.words == 0 {
+            return;
+        }
+        let keep = (1u64 << used) - 1;
+        for y in 0..self.height as usize {
+            let words = self.words;
+            self.row_mut(y)[words - 1] &= keep;
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl ImageAnalyzer {
+    /// Same pixels as [`ImageAnalyzer::detect`], as a mask of the zone
+    pub fn detect_mask(&self, zone: ImageZone, detection: &ColorDetection) -> Mask {
+        let mut mask = Mask::for_zone(&zone, self.image.width(), self.image.height());
+        let (start_x, start_y, end_x, end_y) = zone.bounds(self.image.width(), self.image.height());
+        for y in start_y..end_y {
+            for x in start_x..end_x {
+                if detection.matches_rgb(&Rgb::from(self.image.get_pixel(x, y).0)) {
+                    mask.set_local(x - start_x, y - start_y);
+                }
+            }
+        }
+        mask
+    }
+
+    /// [`ImageAnalyzer::simd_detect`] wrapped in a mask
+    pub fn simd_detect_mask(&self, zone: ImageZone, matchers: &[RgbaMatcher]) -> Mask {
+        let (start_x, start_y, end_x, _) = zone.bounds(self.image.width(), self.image.height());
+        let rows = self.simd_detect(zone, matchers);
+        Mask::from_rows(Point::new(start_x, start_y), end_x - start_x, rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(mask: &mut Mask, x0: u32, y0: u32, size: u32) {
+        for y in y0..y0 + size {
+            for x in x0..x0 + size {
+                mask.set(&Point::new(x, y), true);
+            }
+        }
+    }
+
+    #[test]
+    fn test_morphology() {
+        // Wider than a word to cross word boundaries
+        let mut mask = Mask::new(Point::new(5, 5), 100, 20);
+        square(&mut mask, 60, 8, 6);
+        mask.set(&Point::new(20, 20), true);
+        assert_eq!(mask.count(), 37);
+
+        let opened = mask.open(1);
+        assert_eq!(opened.count(), 36);
+        assert!(!opened.get(&Point::new(20, 20)));
+
+        let eroded = mask.erode(1);
+        assert_eq!(eroded.count(), 16);
+        assert!(eroded.get(&Point::new(61, 9)) && !eroded.get(&Point::new(60, 8)));
+
+        let dilated = mask.dilate(1);
+        assert_eq!(dilated.count(), 64 + 9);
+        assert!(dilated.get(&Point::new(59, 7)) && dilated.get(&Point::new(66, 14)));
+
+        // A one pixel hole is filled by closing
+        let mut holed = mask.clone();
+        holed.set(&Point::new(62, 10), false);
+        assert!(holed.close(1).get(&Point::new(62, 10)));
+
+        // Dilation stops at the mask border, erosion treats outside as unset
+        let mut edge = Mask::new(Point::new(0, 0), 64, 3);
+        square(&mut edge, 61, 0, 3);
+        assert_eq!(edge.dilate(1).count(), 12);
+        assert_eq!(edge.erode(1).points(), vec![Point::new(62, 1)]);
+    }
+
+    #[test]
+    fn test_logic_and_conversions() {
+        let mut a = Mask::new(Point::new(0, 0), 10, 10);
+        let mut b = a.clone();
+        square(&mut a, 0, 0, 4);
+        square(&mut b, 2, 2, 4);
+        assert_eq!(a.and(&b).unwrap().count(), 4);
+        assert_eq!(a.or(&b).unwrap().count(), 28);
+        assert_eq!(a.xor(&b).unwrap().count(), 24);
+        assert!(a.and(&Mask::new(Point::new(1, 0), 10, 10)).is_err());
+
+        let gray = a.to_gray_image();
+        assert_eq!(gray.get_pixel(3, 3).0, [255]);
+        assert_eq!(gray.get_pixel(4, 3).0, [0]);
+        assert_eq!(Mask::from_gray_image(&gray, Point::new(0, 0), 128), a);
+
+        let image = ImageBuffer::from_pixel(10, 10, Rgba([1, 2, 3, 255]));
+        let pixels = a.to_pixels(&image);
+        assert_eq!(pixels.points_count(), 16);
+        assert_eq!(Mask::from_pixels(&pixels, Point::new(0, 0), 10, 10), a);
+        assert_eq!(a.blobs(1).len(), 1);
+    }
+
+    #[test]
+    fn test_detect_mask() {
+        let analyzer = ImageAnalyzer::new(ImageBuffer::from_fn(150, 20, |x, y| {
+            if (x * 7 + y * 3) % 5 == 0 {
+                Rgba([200, 10, 10, 255])
+            } else {
+                Rgba([0, 0, 0, 255])
+            }
+        }));
+        let detection = ColorDetection::rgb(Rgb::from([200, 10, 10]), Rgb::from([0, 0, 0, 0]));
+        let zone = ImageZone::Partial(Point::new(3, 2), Point::new(140, 18));
+        let mask = analyzer.detect_mask(zone.clone(), &detection);
+        assert_eq!(
+            mask.points(),
+            analyzer.detect(zone.clone(), &detection).points()
+        );
+        let matchers = detection.rgba_matchers().unwrap();
+        assert_eq!(analyzer.simd_detect_mask(zone, &matchers), mask);
+    }
+}
@@ -0,0 +1,200 @@
+use crate::data::point::Point;
+
+use super::{blob::extract_blobs, detection::ColorDetection, ImageAnalyzer, ImageZone};
+
+/// A circular minimap and the dots to look for on it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Minimap {
+    pub center: Point,
+    pub radius: u32,
+    /// World units per minimap pixel
+    pub scale: f64,
+    /// Dots with fewer pixels are noise
+    pub min_dot_size: usize,
+    /// Dots closer to the center are ignored, the player marker usually sits there
+    pub dead_zone: f64,
+    pub markers: Vec<(String, ColorDetection)>,
+}
+
+/// A dot found on the minimap
+#[derive(Debug, Clone, PartialEq)]
+pub struct Blip {
+    pub label: String,
+    /// Centroid of the dot in image coordinates
+    pub position: Point,
+    /// Degrees clockwise from up, from 0.0 to 360.0 excluded
+    pub angle: f64,
+    /// Pixels from the center
+    pub distance: f64,
+    /// `(x, y)` from the player in world units, axes oriented as the image
+    pub world_offset: (f64, f64),
+    pub size: usize,
+}
+
+#[allow(dead_code)]
+impl Minimap {
+    pub fn new(center: Point, radius: u32) -> Self {
+        Self {
+            center,
+            radius,
+            scale: 1.0,
+            min_dot_size: 1,
+            dead_zone: 0.0,
+            markers: Vec::new(),
+        }
+    }
+
+    pub fn with_scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn with_min_dot_size(mut self, min_dot_size: usize) -> Self {
+        self.min_dot_size = min_dot_size;
+        self
+    }
+
+    pub fn with_dead_zone(mut self, dead_zone: f64) -> Self {
+        self.dead_zone = dead_zone;
+        self
+    }
+
+    pub fn marker(mut self, label: &str, detection: ColorDetection) -> Self {
+        self.markers.push((label.to_string(), detection));
+        self
+    }
+}
+
+#[allow(dead_code)]
+impl ImageAnalyzer {
+    /// Dots of every marker inside the minimap circle, closest first
+    pub fn read_minimap(&self, minimap: &Minimap) -> Vec<Blip> {
+        let (width, height) = self.image.dimensions();
+        let center = &minimap.center;
+        let zone = ImageZone::Partial(
+            Point::new(
+                center.x.saturating_sub(minimap.radius),
+                center.y.saturating_sub(minimap.radius),
+            ),
+            Point::new(
+                center.x.saturating_add(minimap.radius).saturating_add(1),
+                center.y.saturating_add(minimap.radius).saturating_add(1),
+            ),
+        );
+        // The circle may be partly or entirely outside of the frame
+        let Some(zone) = zone.clip(width, height) else {
+            return Vec::new();
+        };
+
+        let mut blips = Vec::new();
+        for (label, detection) in &minimap.markers {
+            let points: Vec<Point> = self
+                .detect(zone.clone(), detection)
+                .points()
+                .into_iter()
+                .filter(|point| point.distance(center) <= minimap.radius as f64)
+                .collect();
+            for blob in extract_blobs(&points, minimap.min_dot_size) {
+                let distance = blob.centroid.distance(center);
+                if distance < minimap.dead_zone {
+                    continue;
+                }
+                let dx = blob.centroid.x as f64 - center.x as f64;
+                let dy = blob.centroid.y as f64 - center.y as f64;
+                // atan2 of (east, north) gives the compass bearing
+                let angle = dx.atan2(-dy).to_degrees().rem_euclid(360.0);
+                blips.push(Blip {
+                    label: label.clone(),
+                    position: blob.centroid.clone(),
+                    angle,
+                    distance,
+                    world_offset: (dx * minimap.scale, dy * minimap.scale),
+                    size: blob.size(),
+                });
+            }
+        }
+        blips.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        blips
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        data::better_call_zone::Zone, image_analyzer::color::rgb::Rgb, utils::scene::SceneBuilder,
+    };
+
+    #[test]
+    fn test_read_minimap() {
+        let (red, green, white) = (
+            Rgb::from([230, 20, 20]),
+            Rgb::from([20, 230, 20]),
+            Rgb::from([255, 255, 255]),
+        );
+        let scene = SceneBuilder::new(140, 120)
+            .background(Rgb::from([30, 40, 30]))
+            .circle("player", Point::new(60, 60), 2, white)
+            .circle("enemy", Point::new(60, 40), 2, red)
+            .circle("ally", Point::new(90, 60), 2, green)
+            .circle("ally", Point::new(50, 70), 2, green)
+            // Outside of the 40 pixel radius
+            .circle("far", Point::new(60, 105), 2, red)
+            // Too small to be a dot
+            .rect(
+                "speck",
+                Zone::new(Point::new(70, 50), Point::new(71, 51)),
+                red,
+            )
+            .render();
+        let analyzer = ImageAnalyzer::new(scene.image);
+        let exact = |color| ColorDetection::rgb(color, Rgb::from([0, 0, 0, 0]));
+        let minimap = Minimap::new(Point::new(60, 60), 40)
+            .with_scale(2.5)
+            .with_min_dot_size(5)
+            .with_dead_zone(4.0)
+            .marker("enemy", exact(red))
+            .marker("ally", exact(green))
+            .marker("player", exact(white));
+
+        let blips = analyzer.read_minimap(&minimap);
+        let summary: Vec<(&str, f64, f64)> = blips
+            .iter()
+            .map(|blip| (blip.label.as_str(), blip.angle, blip.distance))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("ally", 225.0, 200f64.sqrt()),
+                ("enemy", 0.0, 20.0),
+                ("ally", 90.0, 30.0),
+            ]
+        );
+        assert_eq!(blips[2].world_offset, (75.0, 0.0));
+        assert_eq!(blips[2].size, 13);
+    }
+
+    #[test]
+    fn test_minimap_outside_frame() {
+        let red = Rgb::from([230, 20, 20]);
+        let scene = SceneBuilder::new(140, 120)
+            .background(Rgb::from([30, 40, 30]))
+            .circle("enemy", Point::new(130, 60), 2, red)
+            .render();
+        let analyzer = ImageAnalyzer::new(scene.image);
+        let minimap = |center| {
+            Minimap::new(center, 40)
+                .marker("enemy", ColorDetection::rgb(red, Rgb::from([0, 0, 0, 0])))
+        };
+
+        let blips = analyzer.read_minimap(&minimap(Point::new(150, 60)));
+        assert_eq!(blips.len(), 1);
+        assert_eq!(blips[0].angle, 270.0);
+        assert!(analyzer
+            .read_minimap(&minimap(Point::new(200, 60)))
+            .is_empty());
+        assert!(analyzer
+            .read_minimap(&minimap(Point::new(u32::MAX, u32::MAX)))
+            .is_empty());
+    }
+}
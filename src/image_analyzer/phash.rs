@@ -0,0 +1,288 @@
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+use super::{ImageAnalyzer, ImageZone};
+
+/// 64 bit hashes of a zone, close hashes meaning similar looking zones
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashKind {
+    /// Cells brighter than the mean
+    #[default]
+    Average,
+    /// Cells brighter than their right neighbour, follows gradients
+    Difference,
+    /// Low frequencies of a DCT above their median, the most robust and the slowest
+    Perceptual,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HashEntry {
+    pub label: String,
+    pub hash: u64,
+}
+
+/// Known hashes, an icon set or portraits, looked up by Hamming distance
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct HashLibrary {
+    pub kind: HashKind,
+    pub entries: Vec<HashEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HashMatch {
+    pub label: String,
+    pub distance: u32,
+}
+
+/// Number of differing bits between two hashes
+pub fn hamming(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// Bit per value, the first value being the most significant bit
+fn bits(values: impl Iterator<Item = bool>) -> u64 {
+    values.fold(0, |hash, bit| (hash << 1) | bit as u64)
+}
+
+#[allow(dead_code)]
+impl HashLibrary {
+    pub fn new(kind: HashKind) -> Self {
+        Self {
+            kind,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, label: &str, hash: u64) {
+        self.entries.push(HashEntry {
+            label: label.to_string(),
+            hash,
+        });
+    }
+
+    /// Hashes `zone` of the analyzed frame with the kind of the library
+    pub fn add_zone(&mut self, label: &str, analyzer: &ImageAnalyzer, zone: ImageZone) {
+        self.add(label, analyzer.hash(zone, self.kind));
+    }
+
+    /// Closest entry, the first one added on ties
+    pub fn nearest(&self, hash: u64) -> Option<HashMatch> {
+        self.entries
+            .iter()
+            .min_by_key(|entry| hamming(entry.hash, hash))
+            .map(|entry| HashMatch {
+                label: entry.label.clone(),
+                distance: hamming(entry.hash, hash),
+            })
+    }
+
+    /// Closest entry to `zone` if within `max_distance` bits
+    pub fn lookup(
+        &self,
+        analyzer: &ImageAnalyzer,
+        zone: ImageZone,
+        max_distance: u32,
+    ) -> Option<HashMatch> {
+        self.nearest(analyzer.hash(zone, self.kind))
+            .filter(|found| found.distance <= max_distance)
+    }
+}
+
+#[allow(dead_code)]
+impl ImageAnalyzer {
+    /// Luma of `zone` averaged over a `columns` x `rows` grid, row-major. Zones
+    /// smaller than the grid repeat their pixels, the part outside of the image is
+    /// ignored and a zone without pixels gives a grid of 0.0.
+    fn luma_grid(&self, zone: &ImageZone, columns: u32, rows: u32) -> Vec<f64> {
+        let (image_width, image_height) = self.image.dimensions();
+        let zone = zone.clip(image_width, image_height);
+        let (start_x, start_y, end_x, end_y) =
+            zone.map_or((0, 0, 0, 0), |zone| zone.bounds(image_width, image_height));
+        let (width, height) = (end_x - start_x, end_y - start_y);
+        if width == 0 || height == 0 {
+            return vec![0.0; (columns * rows) as usize];
+        }
+        let range = |start: u32, length: u32, cells: u32, cell: u32| {
+            let from = start + cell * length / cells;
+            let to = (start + (cell + 1) * length / cells).max(from + 1);
+            from..to
+        };
+        let mut grid = Vec::with_capacity((columns * rows) as usize);
+        for row in 0..rows {
+            for column in 0..columns {
+                let (mut sum, mut count) = (0.0, 0);
+                for y in range(start_y, height, rows, row) {
+                    for x in range(start_x, width, columns, column) {
+                        let [r, g, b, _] = self.image.get_pixel(x, y).0;
+                        sum += 0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64;
+                        count += 1;
+                    }
+                }
+                grid.push(sum / count as f64);
+            }
+        }
+        grid
+    }
+
+    /// aHash: one bit per cell of an 8x8 grid, set when the cell is brighter than
+    /// the mean. Insensitive to scaling and to uniform brightness shifts.
+    pub fn average_hash(&self, zone: ImageZone) -> u64 {
+        let grid = self.luma_grid(&zone, 8, 8);
+        let mean = grid.iter().sum::<f64>() / grid.len() as f64;
+        bits(grid.iter().map(|luma| *luma > mean))
+    }
+
+    /// dHash: compares each cell of a 9x8 grid with its right neighbour
+    pub fn difference_hash(&self, zone: ImageZone) -> u64 {
+        let grid = self.luma_grid(&zone, 9, 8);
+        bits(
+            grid.chunks(9)
+                .flat_map(|row| row.windows(2).map(|pair| pair[0] > pair[1])),
+        )
+    }
+
+    /// pHash: 8x8 lowest frequencies of the DCT of a 32x32 grid, set when above the
+    /// median of the frequencies other than the constant one
+    pub fn perceptual_hash(&self, zone: ImageZone) -> u64 {
+        const SIZE: usize = 32;
+        const LOW: usize = 8;
+        let grid = self.luma_grid(&zone, SIZE as u32, SIZE as u32);
+        let basis: Vec<f64> = (0..LOW)
+            .flat_map(|u| {
+                (0..SIZE)
+                    .map(move |x| ((2 * x + 1) as f64 * u as f64 * PI / (2 * SIZE) as f64).cos())
+            })
+            .collect();
+        // Separable DCT-II, rows first, keeping the low frequencies only
+        let rows: Vec<f64> = (0..SIZE)
+            .flat_map(|y| {
+                let grid = &grid;
+                let basis = &basis;
+                (0..LOW).map(move |u| {
+                    (0..SIZE)
+                        .map(|x| grid[y * SIZE + x] * basis[u * SIZE + x])
+                        .sum::<f64>()
+                })
+            })
+            .collect();
+        let coefficients: Vec<f64> = (0..LOW)
+            .flat_map(|v| {
+                let rows = &rows;
+                let basis = &basis;
+                (0..LOW).map(move |u| {
+                    (0..SIZE)
+                        .map(|y| rows[y * LOW + u] * basis[v * SIZE + y])
+                        .sum::<f64>()
+                })
+            })
+            .collect();
+        let mut sorted = coefficients[1..].to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let median = sorted[sorted.len() / 2];
+        bits(coefficients.iter().map(|coefficient| *coefficient > median))
+    }
+
+    pub fn hash(&self, zone: ImageZone, kind: HashKind) -> u64 {
+        match kind {
+            HashKind::Average => self.average_hash(zone),
+            HashKind::Difference => self.difference_hash(zone),
+            HashKind::Perceptual => self.perceptual_hash(zone),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        data::{better_call_zone::Zone, point::Point},
+        image_analyzer::color::rgb::Rgb,
+        utils::scene::{Orientation, SceneBuilder},
+    };
+
+    #[test]
+    fn test_average_hash() {
+        let icon = |background: [u8; 3], shift: u8| {
+            let [r, g, b] = background;
+            SceneBuilder::new(64, 64)
+                .background(Rgb::from([r, g, b]))
+                .rect(
+                    "square",
+                    Zone::new(Point::new(0, 0), Point::new(32, 32)),
+                    Rgb::from([200 + shift, 200 + shift, 200 + shift]),
+                )
+                .render()
+        };
+        let hash = ImageAnalyzer::new(icon([20, 20, 20], 0).image).average_hash(ImageZone::Full);
+        // The top left quarter of the grid is set
+        assert_eq!(hash, 0xf0f0_f0f0_0000_0000);
+        let shifted =
+            ImageAnalyzer::new(icon([40, 35, 30], 30).image).average_hash(ImageZone::Full);
+        assert_eq!(hamming(hash, shifted), 0);
+        // Hashes are relative to the zone, a flat one hashes to 0
+        let analyzer = ImageAnalyzer::new(icon([20, 20, 20], 0).image);
+        let zone = ImageZone::Partial(Point::new(16, 16), Point::new(48, 48));
+        assert_eq!(analyzer.average_hash(zone), hash);
+        let flat = ImageZone::Partial(Point::new(32, 32), Point::new(64, 64));
+        assert_eq!(analyzer.average_hash(flat), 0);
+        let outside = ImageZone::Partial(Point::new(100, 0), Point::new(120, 20));
+        assert_eq!(analyzer.perceptual_hash(outside), 0);
+    }
+
+    /// A one character icon, `scale` times bigger, over a tinted background
+    fn icon(scale: u32, tint: u8, character: &str) -> ImageAnalyzer {
+        let scene = SceneBuilder::new(16 * scale, 16 * scale)
+            .gradient(
+                Rgb::from([30 + tint, 30, 50]),
+                Rgb::from([60 + tint, 50, 80]),
+                Orientation::Vertical,
+            )
+            .text(
+                "glyph",
+                Point::new(2 * scale, scale),
+                character,
+                3 * scale,
+                Rgb::from([220, 200 - tint, 120]),
+            )
+            .render();
+        ImageAnalyzer::new(scene.image)
+    }
+
+    #[test]
+    fn test_hashes_survive_scaling_and_tints() {
+        for kind in [
+            HashKind::Average,
+            HashKind::Difference,
+            HashKind::Perceptual,
+        ] {
+            let reference = icon(1, 0, "A").hash(ImageZone::Full, kind);
+            let scaled = icon(3, 0, "A").hash(ImageZone::Full, kind);
+            let tinted = icon(1, 40, "A").hash(ImageZone::Full, kind);
+            let other = icon(1, 0, "7").hash(ImageZone::Full, kind);
+            assert!(hamming(reference, scaled) <= 4, "{:?}", kind);
+            assert!(hamming(reference, tinted) <= 4, "{:?}", kind);
+            assert!(hamming(reference, other) >= 12, "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn test_library_lookup() {
+        let mut library = HashLibrary::new(HashKind::Perceptual);
+        for character in ["A", "7", "X"] {
+            library.add_zone(character, &icon(2, 0, character), ImageZone::Full);
+        }
+        let found = library
+            .lookup(&icon(3, 30, "7"), ImageZone::Full, 6)
+            .unwrap();
+        assert_eq!(found.label, "7");
+        assert!(library
+            .lookup(&icon(2, 0, "0"), ImageZone::Full, 0)
+            .is_none());
+
+        let json = serde_json::to_string(&library).unwrap();
+        assert!(json.starts_with(r#"{"kind":"perceptual""#), "{}", json);
+        assert_eq!(serde_json::from_str::<HashLibrary>(&json).unwrap(), library);
+    }
+}
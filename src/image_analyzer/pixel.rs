@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
 use crate::data::point::Point;
@@ -8,9 +10,34 @@ pub struct Pixel {
     pub color: Color,
     pub points: Vec<Point>,
 }
+#[derive(Debug, Clone)]
 pub struct PixelVec {
-    pub pixels: Vec<Pixel>,
-    pub points_count: usize,
+    /// Private so that it stays in sync with `index`, read through `pixels()`
+    pixels: Vec<Pixel>,
+    points_count: usize,
+    /// Position in `pixels` of each color, scenes easily hold thousands of colors
+    index: HashMap<ColorKey, usize>,
+}
+
+/// Hashable form of a `Color`, HSV components by their bits
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ColorKey {
+    Rgb([u8; 4]),
+    Hsv([u64; 4]),
+}
+
+impl From<&Color> for ColorKey {
+    fn from(color: &Color) -> Self {
+        match color {
+            Color::Rgb(rgb) => ColorKey::Rgb([rgb.r, rgb.g, rgb.b, rgb.a]),
+            Color::Hsv(hsv) => ColorKey::Hsv([
+                hsv.h.to_bits(),
+                hsv.s.to_bits(),
+                hsv.v.to_bits(),
+                hsv.a.to_bits(),
+            ]),
+        }
+    }
 }
 
 impl PixelVec {
@@ -18,18 +45,49 @@ impl PixelVec {
         Self {
             pixels: Vec::new(),
             points_count: 0,
+            index: HashMap::new(),
         }
     }
 
     pub fn push(&mut self, (color, point): (Color, Point)) {
         self.points_count += 1;
-        if let Some(pixel) = self.pixels.iter_mut().find(|pixel| pixel.color == color) {
-            pixel.points.push(point);
+        let key = ColorKey::from(&color);
+        if let Some(index) = self.index.get(&key) {
+            self.pixels[*index].points.push(point);
             return;
         }
+        self.index.insert(key, self.pixels.len());
         self.pixels.push(Pixel {
             color,
             points: vec![point],
         });
     }
+
+    /// Detected colors with their points, in the order colors were first seen
+    pub fn pixels(&self) -> &[Pixel] {
+        &self.pixels
+    }
+
+    /// Number of points of every color
+    pub fn points_count(&self) -> usize {
+        self.points_count
+    }
+
+    #[allow(dead_code)]
+    pub fn extend(&mut self, other: PixelVec) {
+        for pixel in other.pixels {
+            for point in pixel.points {
+                self.push((pixel.color.clone(), point));
+            }
+        }
+    }
+
+    /// Every point, grouped by color in the order colors were first seen
+    #[allow(dead_code)]
+    pub fn points(&self) -> Vec<Point> {
+        self.pixels
+            .iter()
+            .flat_map(|pixel| pixel.points.iter().cloned())
+            .collect()
+    }
 }
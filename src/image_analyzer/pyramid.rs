@@ -0,0 +1,215 @@
+use image::{ImageBuffer, Rgba};
+
+use crate::data::{
+    better_call_zone::{Zone, ZoneManager},
+    point::Point,
+};
+
+use super::{
+    color::{rgb::Rgb, Color},
+    detection::ColorDetection,
+    pixel::PixelVec,
+    ImageAnalyzer, ImageZone,
+};
+
+/// Downsampled copies of an image, each level half the size of the previous one.
+///
+/// Levels keep the top-left pixel of every 2x2 block, so pixel `(x, y)` of level `n`
+/// is exactly pixel `(x << n, y << n)` of the source image.
+pub struct ImagePyramid {
+    levels: Vec<ImageBuffer<Rgba<u8>, Vec<u8>>>,
+}
+#[allow(dead_code)]
+impl ImagePyramid {
+    pub fn new(image: &ImageBuffer<Rgba<u8>, Vec<u8>>, depth: u32) -> Self {
+        let mut levels: Vec<ImageBuffer<Rgba<u8>, Vec<u8>>> = Vec::new();
+        for _ in 0..depth {
+            let previous = levels.last().unwrap_or(image);
+            levels.push(downsample(previous));
+        }
+        Self { levels }
+    }
+
+    pub fn depth(&self) -> u32 {
+        self.levels.len() as u32
+    }
+
+    /// Level `1` is the first downsampled level, level `depth()` the coarsest one
+    pub fn level(&self, level: u32) -> &ImageBuffer<Rgba<u8>, Vec<u8>> {
+        &self.levels[level as usize - 1]
+    }
+
+    /// Size in source pixels of a pixel of the coarsest level
+    pub fn cell_size(&self) -> u32 {
+        1 << self.depth()
+    }
+}
+
+fn downsample(image: &ImageBuffer<Rgba<u8>, Vec<u8>>) -> ImageBuffer<Rgba<u8>, Vec<u8>> {
+    let width = image.width().div_ceil(2);
+    let height = image.height().div_ceil(2);
+    ImageBuffer::from_fn(width, height, |x, y| *image.get_pixel(x * 2, y * 2))
+}
+
+pub struct PyramidDetection {
+    pub pixels: PixelVec,
+    /// Regions refined at full resolution that contained matches
+    #[allow(dead_code)]
+    pub zones: ZoneManager,
+}
+
+#[allow(dead_code)]
+impl ImageAnalyzer {
+    pub fn pyramid(&self, depth: u32) -> ImagePyramid {
+        ImagePyramid::new(&self.image, depth)
+    }
+
+    /// Coarse-to-fine detection: the coarsest pyramid level selects candidate cells,
+    /// which are then scanned at full resolution and grown into neighbouring cells
+    /// while matches touch their border.
+    ///
+    /// Gives the same pixels, in the same order, as [`ImageAnalyzer::detect`] for every
+    /// connected target at least `2 * cell_size - 1` pixels wide and high. Smaller
+    /// targets may be missed when no sample of the coarse level falls on them.
+    pub fn pyramid_detect(
+        &self,
+        pyramid: &ImagePyramid,
+        zone: ImageZone,
+        detection: &ColorDetection,
+    ) -> PyramidDetection {
+        let width = self.image.width();
+        let height = self.image.height();
+        let (start_x, start_y, end_x, end_y) = zone.bounds(width, height);
+        let cell = pyramid.cell_size();
+        let columns = width.div_ceil(cell);
+        let rows = height.div_ceil(cell);
+        let mut visited = vec![false; (columns * rows) as usize];
+        let mut queue = Vec::new();
+
+        for cy in start_y.div_ceil(cell)..end_y.div_ceil(cell) {
+            for cx in start_x.div_ceil(cell)..end_x.div_ceil(cell) {
+                let pixel = match pyramid.depth() {
+                    0 => self.image.get_pixel(cx, cy),
+                    depth => pyramid.level(depth).get_pixel(cx, cy),
+                };
+                if detection.matches_rgb(&Rgb::from(pixel.0)) {
+                    visited[(cy * columns + cx) as usize] = true;
+                    queue.push((cx, cy));
+                }
+            }
+        }
+
+        let mut matches = Vec::new();
+        let mut zones = ZoneManager::new(width, height);
+        while let Some((cx, cy)) = queue.pop() {
+            let x0 = (cx * cell).max(start_x);
+            let y0 = (cy * cell).max(start_y);
+            let x1 = ((cx + 1) * cell).min(end_x);
+            let y1 = ((cy + 1) * cell).min(end_y);
+            let mut found = false;
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    let rgb = Rgb::from(self.image.get_pixel(x, y).0);
+                    if !detection.matches_rgb(&rgb) {
+                        continue;
+                    }
+                    found = true;
+                    matches.push((rgb, Point { x, y }));
+                    let interior = x > cx * cell
+                        && x + 1 < (cx + 1) * cell
+                        && y > cy * cell
+                        && y + 1 < (cy + 1) * cell;
+                    if interior {
+                        continue;
+                    }
+                    // Grow into every cell holding one of the 8 neighbours of the pixel
+                    for ny in y.saturating_sub(1)..=(y + 1).min(end_y - 1) {
+                        for nx in x.saturating_sub(1)..=(x + 1).min(end_x - 1) {
+                            if nx < start_x || ny < start_y {
+                                continue;
+                            }
+                            let index = ((ny / cell) * columns + nx / cell) as usize;
+                            if !visited[index] {
+                                visited[index] = true;
+                                queue.push((nx / cell, ny / cell));
+                            }
+                        }
+                    }
+                }
+            }
+            if found {
+                zones.add_zone(Zone::new(Point::new(x0, y0), Point::new(x1, y1)));
+            }
+        }
+        zones.merge_overlapping_zones();
+
+        matches.sort_by_key(|(_, point)| (point.y, point.x));
+        let mut pixels = PixelVec::new();
+        for (rgb, point) in matches {
+            pixels.push((Color::Rgb(rgb), point));
+        }
+        PyramidDetection { pixels, zones }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scene() -> ImageBuffer<Rgba<u8>, Vec<u8>> {
+        ImageBuffer::from_fn(203, 117, |x, y| {
+            if (20..61).contains(&x) && (10..40).contains(&y) {
+                Rgba([250, 10, 10, 255])
+            } else if (x as i64 - 150).pow(2) + (y as i64 - 80).pow(2) < 400 {
+                Rgba([240, (x % 8) as u8, 5, 255])
+            } else {
+                Rgba([10, 100, (y % 50) as u8, 255])
+            }
+        })
+    }
+
+    fn assert_same(expected: &PixelVec, actual: &PixelVec) {
+        assert_eq!(expected.points_count(), actual.points_count());
+        assert_eq!(expected.pixels().len(), actual.pixels().len());
+        for (expected, actual) in expected.pixels().iter().zip(actual.pixels().iter()) {
+            assert_eq!(expected.color, actual.color);
+            assert_eq!(expected.points, actual.points);
+        }
+    }
+
+    #[test]
+    fn test_pyramid_levels() {
+        let pyramid = ImagePyramid::new(&scene(), 3);
+        assert_eq!(pyramid.depth(), 3);
+        assert_eq!(pyramid.cell_size(), 8);
+        assert_eq!(pyramid.level(1).dimensions(), (102, 59));
+        assert_eq!(pyramid.level(3).dimensions(), (26, 15));
+        assert_eq!(pyramid.level(3).get_pixel(3, 2), scene().get_pixel(24, 16));
+    }
+
+    #[test]
+    fn test_pyramid_detect_matches_full_scan() {
+        let analyzer = ImageAnalyzer::new(scene());
+        let detection = ColorDetection::rgb(Rgb::from([245, 10, 10]), Rgb::from([10, 10, 10, 0]));
+        let full = analyzer.detect(ImageZone::Full, &detection);
+        assert!(full.points_count() > 0);
+        for depth in 0..4 {
+            let pyramid = analyzer.pyramid(depth);
+            let result = analyzer.pyramid_detect(&pyramid, ImageZone::Full, &detection);
+            assert_same(&full, &result.pixels);
+            assert_eq!(result.zones.zones().len(), 2);
+        }
+    }
+
+    #[test]
+    fn test_pyramid_detect_partial_zone() {
+        let analyzer = ImageAnalyzer::new(scene());
+        let detection = ColorDetection::rgb(Rgb::from([250, 10, 10]), Rgb::from([0, 0, 0, 0]));
+        let zone = ImageZone::Partial(Point::new(30, 5), Point::new(100, 25));
+        let full = analyzer.detect(zone.clone(), &detection);
+        let pyramid = analyzer.pyramid(2);
+        let result = analyzer.pyramid_detect(&pyramid, zone, &detection);
+        assert_eq!(full.points_count(), 31 * 15);
+        assert_same(&full, &result.pixels);
+    }
+}
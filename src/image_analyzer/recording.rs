@@ -0,0 +1,318 @@
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use image::{ImageBuffer, Rgba};
+use serde::{Deserialize, Serialize};
+
+use super::{frame_source::Frame, ImageAnalyzer};
+
+/// File holding the list of frames, next to the frame files
+pub const MANIFEST: &str = "recording.json";
+
+/// Start of the name of every frame file written by a `Recorder`
+const FRAME_PREFIX: &str = "frame_";
+
+/// How frames are stored on disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrameFormat {
+    /// One PNG per frame, readable by any image viewer
+    #[default]
+    Png,
+    /// zlib compressed RGBA bytes, faster to write than PNG
+    Raw,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FrameEntry {
+    pub file: String,
+    /// Time since the start of the recording
+    pub timestamp_ms: u64,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub format: FrameFormat,
+    pub frames: Vec<FrameEntry>,
+}
+
+/// Writes frames into a directory. The manifest is rewritten after every frame so a
+/// session that crashes still leaves a replayable recording.
+pub struct Recorder {
+    directory: PathBuf,
+    manifest: Manifest,
+}
+
+/// Reads back a directory written by a [`Recorder`]
+pub struct Replay {
+    directory: PathBuf,
+    manifest: Manifest,
+}
+
+#[allow(dead_code)]
+impl Recorder {
+    /// Creates the directory if needed. The files of an existing recording in it are
+    /// removed, a directory holding any other file is refused.
+    pub fn create<P: AsRef<Path>>(directory: P, format: FrameFormat) -> Result<Self, String> {
+        let directory = directory.as_ref().to_path_buf();
+        fs::create_dir_all(&directory)
+            .map_err(|error| format!("Cannot create {}: {}", directory.display(), error))?;
+        let read_error =
+            |error: std::io::Error| format!("Cannot read {}: {}", directory.display(), error);
+        let mut previous = Vec::new();
+        for entry in fs::read_dir(&directory).map_err(read_error)? {
+            let path = entry.map_err(read_error)?.path();
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            if !path.is_file() || (name != MANIFEST && !name.starts_with(FRAME_PREFIX)) {
+                return Err(format!(
+                    "{} is not a recording, it holds {}",
+                    directory.display(),
+                    name
+                ));
+            }
+            previous.push(path);
+        }
+        for path in previous {
+            fs::remove_file(&path)
+                .map_err(|error| format!("Cannot remove {}: {}", path.display(), error))?;
+        }
+        let recorder = Self {
+            directory,
+            manifest: Manifest {
+                format,
+                frames: Vec::new(),
+            },
+        };
+        recorder.write_manifest()?;
+        Ok(recorder)
+    }
+
+    pub fn record(
+        &mut self,
+        image: &ImageBuffer<Rgba<u8>, Vec<u8>>,
+        timestamp: Duration,
+    ) -> Result<(), String> {
+        let index = self.manifest.frames.len();
+        let file = match self.manifest.format {
+            FrameFormat::Png => format!("frame_{:05}.png", index),
+            FrameFormat::Raw => format!("frame_{:05}.rgba.z", index),
+        };
+        let path = self.directory.join(&file);
+        let error = |error: String| format!("Cannot write {}: {}", path.display(), error);
+        match self.manifest.format {
+            FrameFormat::Png => image.save(&path).map_err(|e| error(e.to_string()))?,
+            FrameFormat::Raw => {
+                let writer = BufWriter::new(File::create(&path).map_err(|e| error(e.to_string()))?);
+                let mut encoder = ZlibEncoder::new(writer, Compression::fast());
+                encoder
+                    .write_all(image.as_raw())
+                    .and_then(|_| encoder.finish())
+                    .and_then(|mut writer| writer.flush())
+                    .map_err(|e| error(e.to_string()))?;
+            }
+        }
+        self.manifest.frames.push(FrameEntry {
+            file,
+            timestamp_ms: timestamp.as_millis() as u64,
+            width: image.width(),
+            height: image.height(),
+        });
+        self.write_manifest()
+    }
+
+    pub fn len(&self) -> usize {
+        self.manifest.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.manifest.frames.is_empty()
+    }
+
+    fn write_manifest(&self) -> Result<(), String> {
+        let path = self.directory.join(MANIFEST);
+        let json = serde_json::to_string_pretty(&self.manifest).unwrap();
+        fs::write(&path, json)
+            .map_err(|error| format!("Cannot write {}: {}", path.display(), error))
+    }
+}
+
+#[allow(dead_code)]
+impl Replay {
+    pub fn open<P: AsRef<Path>>(directory: P) -> Result<Self, String> {
+        let directory = directory.as_ref().to_path_buf();
+        let path = directory.join(MANIFEST);
+        let text = fs::read_to_string(&path)
+            .map_err(|error| format!("Cannot read {}: {}", path.display(), error))?;
+        let manifest: Manifest = serde_json::from_str(&text)
+            .map_err(|error| format!("Invalid {}: {}", path.display(), error))?;
+        // Frame files are read from the directory only, not from `../` or absolute paths
+        for entry in &manifest.frames {
+            if Path::new(&entry.file).file_name() != Some(entry.file.as_ref()) {
+                return Err(format!(
+                    "Invalid {}: {} is not a file name",
+                    path.display(),
+                    entry.file
+                ));
+            }
+        }
+        Ok(Self {
+            directory,
+            manifest,
+        })
+    }
+
+    pub fn manifest(&self) -> &Manifest {
+        &self.manifest
+    }
+
+    pub fn len(&self) -> usize {
+        self.manifest.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.manifest.frames.is_empty()
+    }
+
+    pub fn frame(&self, index: usize) -> Result<Frame, String> {
+        let entry = self
+            .manifest
+            .frames
+            .get(index)
+            .ok_or(format!("No frame {} in the recording", index))?;
+        let path = self.directory.join(&entry.file);
+        let error = |error: String| format!("Cannot read {}: {}", path.display(), error);
+        let image = match self.manifest.format {
+            FrameFormat::Png => image::open(&path)
+                .map_err(|e| error(e.to_string()))?
+                .to_rgba8(),
+            FrameFormat::Raw => {
+                let reader = BufReader::new(File::open(&path).map_err(|e| error(e.to_string()))?);
+                let mut raw = Vec::new();
+                ZlibDecoder::new(reader)
+                    .read_to_end(&mut raw)
+                    .map_err(|e| error(e.to_string()))?;
+                ImageBuffer::from_raw(entry.width, entry.height, raw)
+                    .ok_or(error("size does not match the manifest".to_string()))?
+            }
+        };
+        if image.dimensions() != (entry.width, entry.height) {
+            return Err(error("size does not match the manifest".to_string()));
+        }
+        Ok(Frame {
+            image,
+            timestamp: Duration::from_millis(entry.timestamp_ms),
+        })
+    }
+
+    pub fn frames(&self) -> impl Iterator<Item = Result<Frame, String>> + '_ {
+        (0..self.len()).map(|index| self.frame(index))
+    }
+
+    /// Feeds every frame to `analyzer` with [`ImageAnalyzer::next_frame`], as live
+    /// capture does, and calls `on_frame` after each one
+    pub fn run<F>(&self, analyzer: &mut ImageAnalyzer, mut on_frame: F) -> Result<(), String>
+    where
+        F: FnMut(&ImageAnalyzer, Duration),
+    {
+        for frame in self.frames() {
+            let frame = frame?;
+            analyzer.next_frame(frame.image);
+            on_frame(analyzer, frame.timestamp);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image_analyzer::{color::rgb::Rgb, detection::ColorDetection, ImageZone};
+
+    fn frame(index: u32) -> ImageBuffer<Rgba<u8>, Vec<u8>> {
+        ImageBuffer::from_fn(40, 30, |x, y| {
+            if x == index * 5 && y < 10 {
+                Rgba([255, 0, 0, 255])
+            } else {
+                Rgba([(x * 6) as u8, (y * 8) as u8, 40, 255])
+            }
+        })
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        for format in [FrameFormat::Png, FrameFormat::Raw] {
+            let directory =
+                std::env::temp_dir().join(format!("recording_{:?}_{}", format, std::process::id()));
+            let mut recorder = Recorder::create(&directory, format).unwrap();
+            for index in 0..3 {
+                recorder
+                    .record(&frame(index), Duration::from_millis(index as u64 * 16))
+                    .unwrap();
+            }
+
+            let replay = Replay::open(&directory).unwrap();
+            assert_eq!(replay.len(), 3);
+            let second = replay.frame(1).unwrap();
+            assert!(second.image == frame(1), "{:?}", format);
+            assert_eq!(second.timestamp, Duration::from_millis(16));
+            assert!(replay.frame(3).is_err());
+
+            let red = ColorDetection::rgb(Rgb::from([255, 0, 0]), Rgb::from([0, 0, 0, 0]));
+            let mut analyzer = ImageAnalyzer::new(ImageBuffer::new(40, 30));
+            let mut seen = Vec::new();
+            replay
+                .run(&mut analyzer, |analyzer, timestamp| {
+                    let pixels = analyzer.detect(ImageZone::Full, &red);
+                    seen.push((timestamp.as_millis(), pixels.points()[0].x));
+                })
+                .unwrap();
+            assert_eq!(seen, vec![(0, 0), (16, 5), (32, 10)]);
+            assert!(analyzer.previous.is_some());
+
+            fs::remove_dir_all(&directory).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_recording_directory() {
+        let directory = std::env::temp_dir().join(format!("recording_dir_{}", std::process::id()));
+        let mut recorder = Recorder::create(&directory, FrameFormat::Raw).unwrap();
+        for index in 0..3 {
+            recorder.record(&frame(index), Duration::ZERO).unwrap();
+        }
+        // A shorter session recorded over it leaves no stale frame
+        let mut recorder = Recorder::create(&directory, FrameFormat::Raw).unwrap();
+        recorder.record(&frame(0), Duration::ZERO).unwrap();
+        assert_eq!(fs::read_dir(&directory).unwrap().count(), 2);
+
+        let manifest = |file: &str| {
+            format!(
+                r#"{{"format": "raw", "frames": [{{"file": "{}", "timestamp_ms": 0, "width": 40, "height": 30}}]}}"#,
+                file
+            )
+        };
+        for file in [
+            "../frame_00000.rgba.z",
+            "/tmp/frame_00000.rgba.z",
+            "frames/frame_00000.rgba.z",
+        ] {
+            fs::write(directory.join(MANIFEST), manifest(file)).unwrap();
+            assert!(Replay::open(&directory).is_err(), "{}", file);
+        }
+        fs::write(directory.join(MANIFEST), manifest("frame_00000.rgba.z")).unwrap();
+        assert!(Replay::open(&directory).unwrap().frame(0).unwrap().image == frame(0));
+
+        fs::write(directory.join("notes.txt"), "not a frame").unwrap();
+        assert!(Recorder::create(&directory, FrameFormat::Raw).is_err());
+        assert!(directory.join(MANIFEST).exists());
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
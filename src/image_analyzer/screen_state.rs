@@ -0,0 +1,380 @@
+use std::{collections::VecDeque, time::Duration};
+
+use super::{
+    detection::ColorDetection,
+    phash::{hamming, HashKind},
+    ImageAnalyzer, ImageZone,
+};
+
+/// Transitions kept by a `ScreenClassifier`, oldest dropped first
+const HISTORY: usize = 256;
+
+/// One clue that a given screen is displayed
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ZoneCheck {
+    /// The share of the zone matching `detection` is between `min_ratio` and `max_ratio`
+    Color {
+        zone: ImageZone,
+        detection: ColorDetection,
+        min_ratio: f64,
+        max_ratio: f64,
+    },
+    /// The hash of the zone is within `max_distance` bits of `hash`
+    Hash {
+        zone: ImageZone,
+        kind: HashKind,
+        hash: u64,
+        max_distance: u32,
+    },
+}
+
+/// A screen of the client recognized by all of its checks
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScreenState {
+    pub name: String,
+    pub checks: Vec<ZoneCheck>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Classification {
+    /// Best state, `None` when its score is below the classifier minimum
+    pub state: Option<String>,
+    pub score: f64,
+    /// Score of every state, in definition order
+    pub scores: Vec<(String, f64)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transition {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub timestamp: Duration,
+}
+
+/// Tells which screen a frame shows and remembers when it changed
+#[derive(Debug, Clone)]
+pub struct ScreenClassifier {
+    states: Vec<ScreenState>,
+    min_score: f64,
+    current: Option<String>,
+    history: VecDeque<Transition>,
+}
+
+#[allow(dead_code)]
+impl ZoneCheck {
+    /// 1.0 when the check passes, decreasing the further the frame is from passing. Zones
+    /// are clipped to the frame, one entirely outside of it scores 0.0.
+    pub fn score(&self, analyzer: &ImageAnalyzer) -> f64 {
+        let (width, height) = analyzer.image.dimensions();
+        let zone = match self {
+            ZoneCheck::Color { zone, .. } | ZoneCheck::Hash { zone, .. } => zone,
+        };
+        let Some(zone) = zone.clip(width, height) else {
+            return 0.0;
+        };
+        match self {
+            ZoneCheck::Color {
+                detection,
+                min_ratio,
+                max_ratio,
+                ..
+            } => {
+                let (start_x, start_y, end_x, end_y) = zone.bounds(width, height);
+                let area = ((end_x - start_x) * (end_y - start_y)).max(1) as f64;
+                let ratio = analyzer.detect(zone, detection).points_count() as f64 / area;
+                if ratio < *min_ratio {
+                    ratio / min_ratio
+                } else if ratio > *max_ratio {
+                    (1.0 - ratio) / (1.0 - max_ratio)
+                } else {
+                    1.0
+                }
+            }
+            ZoneCheck::Hash {
+                kind,
+                hash,
+                max_distance,
+                ..
+            } => {
+                let distance = hamming(analyzer.hash(zone, *kind), *hash);
+                if distance <= *max_distance {
+                    1.0
+                } else {
+                    1.0 - (distance - max_distance) as f64 / (64 - max_distance) as f64
+                }
+            }
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl ScreenState {
+    pub fn new(name: &str, checks: Vec<ZoneCheck>) -> Self {
+        Self {
+            name: name.to_string(),
+            checks,
+        }
+    }
+
+    /// Mean score of the checks, 0.0 without any
+    pub fn score(&self, analyzer: &ImageAnalyzer) -> f64 {
+        if self.checks.is_empty() {
+            return 0.0;
+        }
+        let total: f64 = self.checks.iter().map(|check| check.score(analyzer)).sum();
+        total / self.checks.len() as f64
+    }
+}
+
+#[allow(dead_code)]
+impl ScreenClassifier {
+    /// States scoring below `min_score` are not recognized, 1.0 requires every check
+    /// to pass
+    pub fn new(states: Vec<ScreenState>, min_score: f64) -> Self {
+        Self {
+            states,
+            min_score,
+            current: None,
+            history: VecDeque::new(),
+        }
+    }
+
+    pub fn current(&self) -> Option<&str> {
+        self.current.as_deref()
+    }
+
+    pub fn history(&self) -> impl Iterator<Item = &Transition> {
+        self.history.iter()
+    }
+
+    /// Scores every state without recording anything, the first best state wins ties
+    pub fn evaluate(&self, analyzer: &ImageAnalyzer) -> Classification {
+        let scores: Vec<(String, f64)> = self
+            .states
+            .iter()
+            .map(|state| (state.name.clone(), state.score(analyzer)))
+            .collect();
+        let best = scores
+            .iter()
+            .fold(None, |best: Option<&(String, f64)>, entry| match best {
+                Some(best) if best.1 >= entry.1 => Some(best),
+                _ => Some(entry),
+            });
+        let score = best.map_or(0.0, |best| best.1);
+        Classification {
+            state: best
+                .filter(|best| best.1 >= self.min_score)
+                .map(|best| best.0.clone()),
+            score,
+            scores,
+        }
+    }
+
+    /// Classifies the frame shown at `timestamp`, recording a transition when the
+    /// recognized state differs from the previous frame
+    pub fn classify(&mut self, analyzer: &ImageAnalyzer, timestamp: Duration) -> Classification {
+        let classification = self.evaluate(analyzer);
+        if classification.state != self.current {
+            if self.history.len() == HISTORY {
+                self.history.pop_front();
+            }
+            self.history.push_back(Transition {
+                from: self.current.take(),
+                to: classification.state.clone(),
+                timestamp,
+            });
+            self.current = classification.state.clone();
+        }
+        classification
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        data::{better_call_zone::Zone, point::Point},
+        image_analyzer::color::rgb::Rgb,
+        utils::scene::{Orientation, Scene, SceneBuilder},
+    };
+
+    const HUD: Rgb = Rgb {
+        r: 180,
+        g: 30,
+        b: 30,
+        a: 255,
+    };
+    const DIALOG: Rgb = Rgb {
+        r: 230,
+        g: 220,
+        b: 180,
+        a: 255,
+    };
+
+    fn login() -> Scene {
+        SceneBuilder::new(200, 150)
+            .background(Rgb::from([10, 10, 40]))
+            .rect(
+                "panel",
+                Zone::new(Point::new(60, 40), Point::new(140, 110)),
+                Rgb::from([90, 90, 160]),
+            )
+            .text(
+                "title",
+                Point::new(70, 50),
+                "LOGIN",
+                2,
+                Rgb::from([250, 250, 250]),
+            )
+            .render()
+    }
+
+    fn world(dialog: bool) -> Scene {
+        let mut builder = SceneBuilder::new(200, 150)
+            .gradient(
+                Rgb::from([30, 90, 30]),
+                Rgb::from([60, 120, 40]),
+                Orientation::Vertical,
+            )
+            .noise(6)
+            .random_shapes(8, &[Rgb::from([120, 80, 40])])
+            .rect(
+                "hud",
+                Zone::new(Point::new(0, 135), Point::new(200, 150)),
+                HUD,
+            );
+        if dialog {
+            builder = builder.rect(
+                "dialog",
+                Zone::new(Point::new(50, 40), Point::new(150, 100)),
+                DIALOG,
+            );
+        }
+        builder.render()
+    }
+
+    fn exact(color: Rgb) -> ColorDetection {
+        ColorDetection::rgb(color, Rgb::from([4, 4, 4, 0]))
+    }
+
+    fn classifier() -> ScreenClassifier {
+        let panel = ImageZone::Partial(Point::new(40, 20), Point::new(160, 130));
+        let login_hash = ImageAnalyzer::new(login().image).average_hash(panel.clone());
+        let hud = ZoneCheck::Color {
+            zone: ImageZone::Partial(Point::new(0, 135), Point::new(200, 150)),
+            detection: exact(HUD),
+            min_ratio: 0.9,
+            max_ratio: 1.0,
+        };
+        let dialog = |min_ratio, max_ratio| ZoneCheck::Color {
+            zone: ImageZone::Partial(Point::new(60, 50), Point::new(140, 90)),
+            detection: exact(DIALOG),
+            min_ratio,
+            max_ratio,
+        };
+        ScreenClassifier::new(
+            vec![
+                ScreenState::new(
+                    "login",
+                    vec![ZoneCheck::Hash {
+                        zone: panel,
+                        kind: HashKind::Average,
+                        hash: login_hash,
+                        max_distance: 4,
+                    }],
+                ),
+                ScreenState::new("world", vec![hud.clone(), dialog(0.0, 0.1)]),
+                ScreenState::new("dialog", vec![hud, dialog(0.9, 1.0)]),
+            ],
+            1.0,
+        )
+    }
+
+    #[test]
+    fn test_classify_screens() {
+        let mut classifier = classifier();
+        let frames = [
+            login(),
+            world(false),
+            world(false),
+            world(true),
+            world(false),
+        ];
+        let states: Vec<Option<String>> = frames
+            .iter()
+            .enumerate()
+            .map(|(index, scene)| {
+                let analyzer = ImageAnalyzer::new(scene.image.clone());
+                let timestamp = Duration::from_secs(index as u64);
+                classifier.classify(&analyzer, timestamp).state
+            })
+            .collect();
+        let named = |name: &str| Some(name.to_string());
+        assert_eq!(
+            states,
+            vec![
+                named("login"),
+                named("world"),
+                named("world"),
+                named("dialog"),
+                named("world")
+            ]
+        );
+        let transitions: Vec<(Option<String>, u64)> = classifier
+            .history()
+            .map(|transition| (transition.to.clone(), transition.timestamp.as_secs()))
+            .collect();
+        assert_eq!(
+            transitions,
+            vec![
+                (named("login"), 0),
+                (named("world"), 1),
+                (named("dialog"), 3),
+                (named("world"), 4)
+            ]
+        );
+        assert_eq!(classifier.current(), Some("world"));
+    }
+
+    #[test]
+    fn test_unknown_screen() {
+        let classifier = classifier();
+        let blank = SceneBuilder::new(200, 150)
+            .background(Rgb::from([0, 0, 0]))
+            .render();
+        let classification = classifier.evaluate(&ImageAnalyzer::new(blank.image));
+        assert_eq!(classification.state, None);
+        assert!(classification.score < 1.0);
+        assert_eq!(classification.scores.len(), 3);
+    }
+
+    #[test]
+    fn test_checks_outside_of_the_frame() {
+        let analyzer = ImageAnalyzer::new(world(false).image);
+        let outside = ImageZone::Partial(Point::new(300, 0), Point::new(400, 20));
+        let color = ZoneCheck::Color {
+            zone: outside.clone(),
+            detection: exact(HUD),
+            min_ratio: 0.0,
+            max_ratio: 1.0,
+        };
+        let hash = ZoneCheck::Hash {
+            zone: outside,
+            kind: HashKind::Perceptual,
+            hash: 0,
+            max_distance: 64,
+        };
+        assert_eq!(color.score(&analyzer), 0.0);
+        assert_eq!(hash.score(&analyzer), 0.0);
+
+        // Only the part inside of the frame is checked
+        let clipped = ZoneCheck::Color {
+            zone: ImageZone::Partial(Point::new(0, 135), Point::new(400, 300)),
+            detection: exact(HUD),
+            min_ratio: 0.9,
+            max_ratio: 1.0,
+        };
+        assert_eq!(clipped.score(&analyzer), 1.0);
+    }
+}
@@ -0,0 +1,293 @@
+use crate::data::point::Point;
+
+use super::{
+    color::{rgb::Rgb, Color},
+    detection::ColorDetection,
+    pixel::PixelVec,
+    ImageAnalyzer, ImageZone,
+};
+
+/// Instruction set used by an [`RgbaMatcher`]
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SimdLevel {
+    Scalar,
+    Sse2,
+    Avx2,
+}
+#[allow(dead_code)]
+impl SimdLevel {
+    /// Best level supported by the running CPU
+    pub fn detect() -> Self {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx2") {
+                return SimdLevel::Avx2;
+            }
+            if is_x86_feature_detected!("sse2") {
+                return SimdLevel::Sse2;
+            }
+        }
+        SimdLevel::Scalar
+    }
+
+    fn supported(&self) -> bool {
+        match self {
+            SimdLevel::Scalar => true,
+            #[cfg(target_arch = "x86_64")]
+            SimdLevel::Sse2 => is_x86_feature_detected!("sse2"),
+            #[cfg(target_arch = "x86_64")]
+            SimdLevel::Avx2 => is_x86_feature_detected!("avx2"),
+            #[cfg(not(target_arch = "x86_64"))]
+            _ => false,
+        }
+    }
+}
+
+/// Compares raw RGBA bytes against a reference color, same result as `Rgb::compare`.
+///
+/// `std::simd` is not stable yet, so the vector paths use `std::arch` intrinsics and
+/// are picked at runtime, with a scalar fallback on other CPUs and architectures.
+#[derive(Debug, Clone)]
+pub struct RgbaMatcher {
+    reference: [u8; 4],
+    tolerance: [u8; 4],
+    level: SimdLevel,
+}
+#[allow(dead_code)]
+impl RgbaMatcher {
+    pub fn new(reference: Rgb, tolerance: Rgb) -> Self {
+        Self::with_level(reference, tolerance, SimdLevel::detect())
+    }
+
+    /// Forces an instruction set, falls back to scalar when the CPU lacks it
+    pub fn with_level(reference: Rgb, tolerance: Rgb, level: SimdLevel) -> Self {
+        let level = if level.supported() {
+            level
+        } else {
+            SimdLevel::Scalar
+        };
+        Self {
+            reference: [reference.r, reference.g, reference.b, reference.a],
+            tolerance: [tolerance.r, tolerance.g, tolerance.b, tolerance.a],
+            level,
+        }
+    }
+
+    pub fn level(&self) -> SimdLevel {
+        self.level
+    }
+
+    /// Sets bit `x % 64` of `mask[x / 64]` for every matching pixel `x` of an RGBA row.
+    /// `mask` must hold at least `row.len() / 4` bits and is not cleared beforehand.
+    pub fn match_row(&self, row: &[u8], mask: &mut [u64]) {
+        // SAFETY: `with_level` only keeps a level the running CPU supports
+        let done = match self.level {
+            #[cfg(target_arch = "x86_64")]
+            SimdLevel::Avx2 => unsafe {
+                x86::match_avx2(row, self.reference, self.tolerance, mask)
+            },
+            #[cfg(target_arch = "x86_64")]
+            SimdLevel::Sse2 => unsafe {
+                x86::match_sse2(row, self.reference, self.tolerance, mask)
+            },
+            _ => 0,
+        };
+        self.match_scalar(row, done, mask);
+    }
+
+    pub fn match_row_mask(&self, row: &[u8]) -> Vec<u64> {
+        let mut mask = vec![0; (row.len() / 4).div_ceil(64)];
+        self.match_row(row, &mut mask);
+        mask
+    }
+
+    fn match_scalar(&self, row: &[u8], from: usize, mask: &mut [u64]) {
+        for (index, pixel) in row.chunks_exact(4).enumerate().skip(from) {
+            let matches = (0..4).all(|c| pixel[c].abs_diff(self.reference[c]) <= self.tolerance[c]);
+            if matches {
+                mask[index / 64] |= 1 << (index % 64);
+            }
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use std::arch::x86_64::*;
+
+    /// Matches groups of 4 pixels, returns the number of pixels handled
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn match_sse2(
+        row: &[u8],
+        reference: [u8; 4],
+        tolerance: [u8; 4],
+        mask: &mut [u64],
+    ) -> usize {
+        let reference = _mm_set1_epi32(i32::from_le_bytes(reference));
+        let tolerance = _mm_set1_epi32(i32::from_le_bytes(tolerance));
+        let ones = _mm_set1_epi32(-1);
+        let chunks = row.len() / 16;
+        for chunk in 0..chunks {
+            let pixels = _mm_loadu_si128(row.as_ptr().add(chunk * 16) as *const __m128i);
+            // |a - b| on unsigned bytes, then diff <= tolerance as min(diff, tolerance) == diff
+            let diff = _mm_or_si128(
+                _mm_subs_epu8(pixels, reference),
+                _mm_subs_epu8(reference, pixels),
+            );
+            let within = _mm_cmpeq_epi8(_mm_min_epu8(diff, tolerance), diff);
+            let matched = _mm_cmpeq_epi32(within, ones);
+            let bits = _mm_movemask_ps(_mm_castsi128_ps(matched)) as u64;
+            let index = chunk * 4;
+            mask[index / 64] |= bits << (index % 64);
+        }
+        chunks * 4
+    }
+
+    /// Matches groups of 8 pixels, returns the number of pixels handled
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn match_avx2(
+        row: &[u8],
+        reference: [u8; 4],
+        tolerance: [u8; 4],
+        mask: &mut [u64],
+    ) -> usize {
+        let reference = _mm256_set1_epi32(i32::from_le_bytes(reference));
+        let tolerance = _mm256_set1_epi32(i32::from_le_bytes(tolerance));
+        let ones = _mm256_set1_epi32(-1);
+        let chunks = row.len() / 32;
+        for chunk in 0..chunks {
+            let pixels = _mm256_loadu_si256(row.as_ptr().add(chunk * 32) as *const __m256i);
+            let diff = _mm256_or_si256(
+                _mm256_subs_epu8(pixels, reference),
+                _mm256_subs_epu8(reference, pixels),
+            );
+            let within = _mm256_cmpeq_epi8(_mm256_min_epu8(diff, tolerance), diff);
+            let matched = _mm256_cmpeq_epi32(within, ones);
+            let bits = _mm256_movemask_ps(_mm256_castsi256_ps(matched)) as u64;
+            let index = chunk * 8;
+            mask[index / 64] |= bits << (index % 64);
+        }
+        chunks * 8
+    }
+}
+
+#[allow(dead_code)]
+impl ColorDetection {
+    /// One matcher per alternative, `None` when an alternative is not in RGB mode
+    pub fn rgba_matchers(&self) -> Option<Vec<RgbaMatcher>> {
+        self.alternatives()
+            .into_iter()
+            .map(|detection| match detection {
+                ColorDetection::Rgb { color, tolerance } => {
+                    Some(RgbaMatcher::new(*color, *tolerance))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[allow(dead_code)]
+impl ImageAnalyzer {
+    /// Match bitmask of every row of the zone, bit `0` being the first column of the zone.
+    /// A pixel is set when any of the matchers accepts it.
+    pub fn simd_detect(&self, zone: ImageZone, matchers: &[RgbaMatcher]) -> Vec<Vec<u64>> {
+        let (start_x, start_y, end_x, end_y) = zone.bounds(self.image.width(), self.image.height());
+        let raw: &[u8] = &self.image;
+        let stride = self.image.width() as usize * 4;
+        let words = ((end_x - start_x) as usize).div_ceil(64);
+        (start_y..end_y)
+            .map(|y| {
+                let row = y as usize * stride;
+                let row = &raw[row + start_x as usize * 4..row + end_x as usize * 4];
+                let mut mask = vec![0; words];
+                for matcher in matchers {
+                    matcher.match_row(row, &mut mask);
+                }
+                mask
+            })
+            .collect()
+    }
+
+    /// Same pixels as [`ImageAnalyzer::detect`] with an RGB detection, through the SIMD masks
+    pub fn simd_detect_pixels(&self, zone: ImageZone, matchers: &[RgbaMatcher]) -> PixelVec {
+        let (start_x, start_y, _, _) = zone.bounds(self.image.width(), self.image.height());
+        let mut points = PixelVec::new();
+        for (row, mask) in self.simd_detect(zone, matchers).iter().enumerate() {
+            for (word_index, word) in mask.iter().enumerate() {
+                let mut word = *word;
+                while word != 0 {
+                    let bit = word.trailing_zeros();
+                    word &= word - 1;
+                    let x = start_x + word_index as u32 * 64 + bit;
+                    let y = start_y + row as u32;
+                    let rgb = Rgb::from(self.image.get_pixel(x, y).0);
+                    points.push((Color::Rgb(rgb), Point { x, y }));
+                }
+            }
+        }
+        points
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{ImageBuffer, Rgba};
+
+    use super::*;
+
+    fn noise(length: usize) -> Vec<u8> {
+        let mut state: u32 = 0x1234_5678;
+        (0..length)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                // Keep values around the reference so both outcomes are frequent
+                120 + (state >> 24) as u8 % 24
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_levels_agree_with_rgb_compare() {
+        let reference = Rgb::from([130, 128, 132, 130]);
+        let tolerance = Rgb::from([8, 6, 7, 9]);
+        let row = noise(4 * 203);
+        for level in [SimdLevel::Scalar, SimdLevel::Sse2, SimdLevel::Avx2] {
+            let matcher = RgbaMatcher::with_level(reference, tolerance, level);
+            let mask = matcher.match_row_mask(&row);
+            for (x, pixel) in row.chunks_exact(4).enumerate() {
+                let expected = reference.compare(
+                    &Rgb::from([pixel[0], pixel[1], pixel[2], pixel[3]]),
+                    tolerance,
+                );
+                assert_eq!(
+                    mask[x / 64] >> (x % 64) & 1 == 1,
+                    expected,
+                    "{:?} pixel {}",
+                    level,
+                    x
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_simd_detect_matches_detect() {
+        let raw = noise(4 * 97 * 31);
+        let image = ImageBuffer::<Rgba<u8>, Vec<u8>>::from_raw(97, 31, raw).unwrap();
+        let analyzer = ImageAnalyzer::new(image);
+        let reference = Rgb::from([130, 128, 132, 130]);
+        let tolerance = Rgb::from([10, 10, 10, 12]);
+        let zone = ImageZone::Partial(Point::new(3, 2), Point::new(90, 30));
+
+        let expected = analyzer.detect(zone.clone(), &ColorDetection::rgb(reference, tolerance));
+        let actual = analyzer.simd_detect_pixels(zone, &[RgbaMatcher::new(reference, tolerance)]);
+        assert!(expected.points_count() > 0);
+        assert_eq!(expected.points_count(), actual.points_count());
+        for (expected, actual) in expected.pixels().iter().zip(actual.pixels().iter()) {
+            assert_eq!(expected.color, actual.color);
+            assert_eq!(expected.points, actual.points);
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use image::{ImageBuffer, Rgba};
+
+use crate::data::point::Point;
+
+use super::{ImageAnalyzer, ImageZone};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateMatch {
+    /// Top left corner of the matched area
+    pub position: Point,
+    /// Mean absolute difference per RGB channel, from 0.0 (identical) to 255.0
+    pub error: f64,
+}
+
+#[allow(dead_code)]
+impl ImageAnalyzer {
+    /// Best position of `template` fully inside `zone`, by sum of absolute
+    /// differences. Brute force, keep the zone or the template small.
+    pub fn match_template(
+        &self,
+        template: &ImageBuffer<Rgba<u8>, Vec<u8>>,
+        zone: ImageZone,
+    ) -> Option<TemplateMatch> {
+        let (start_x, start_y, end_x, end_y) = zone.bounds(self.image.width(), self.image.height());
+        let (width, height) = template.dimensions();
+        if width == 0 || height == 0 || end_x - start_x < width || end_y - start_y < height {
+            return None;
+        }
+        let mut best: Option<(Point, u64)> = None;
+        for y in start_y..=end_y - height {
+            for x in start_x..=end_x - width {
+                let limit = best.as_ref().map_or(u64::MAX, |best| best.1);
+                let mut sum = 0;
+                'rows: for ty in 0..height {
+                    for tx in 0..width {
+                        let [r, g, b, _] = self.image.get_pixel(x + tx, y + ty).0;
+                        let [tr, tg, tb, _] = template.get_pixel(tx, ty).0;
+                        sum += (r.abs_diff(tr) as u64)
+                            + (g.abs_diff(tg) as u64)
+                            + (b.abs_diff(tb) as u64);
+                    }
+                    // Already worse than the best position
+                    if sum >= limit {
+                        break 'rows;
+                    }
+                }
+                if sum < limit {
+                    best = Some((Point::new(x, y), sum));
+                }
+            }
+        }
+        best.map(|(position, sum)| TemplateMatch {
+            position,
+            error: sum as f64 / (width * height * 3) as f64,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::imageops;
+
+    use super::*;
+    use crate::{
+        data::better_call_zone::Zone,
+        image_analyzer::color::rgb::Rgb,
+        utils::scene::{Orientation, SceneBuilder},
+    };
+
+    #[test]
+    fn test_match_template() {
+        let scene = |x: u32, y: u32| {
+            SceneBuilder::new(120, 90)
+                .gradient(
+                    Rgb::from([20, 30, 40]),
+                    Rgb::from([80, 60, 40]),
+                    Orientation::Horizontal,
+                )
+                .noise(5)
+                .rect(
+                    "frame",
+                    Zone::new(Point::new(x, y), Point::new(x + 24, y + 12)),
+                    Rgb::from([150, 150, 170]),
+                )
+                .text(
+                    "icon",
+                    Point::new(x + 2, y + 3),
+                    "OK",
+                    1,
+                    Rgb::from([250, 240, 0]),
+                )
+                .render()
+                .image
+        };
+        let template = imageops::crop_imm(&scene(10, 10), 10, 10, 24, 12).to_image();
+        let analyzer = ImageAnalyzer::new(scene(70, 55));
+        let found = analyzer.match_template(&template, ImageZone::Full).unwrap();
+        assert_eq!(found.position, Point::new(70, 55));
+        assert_eq!(found.error, 0.0);
+
+        // Best match in a zone without the panel is poor
+        let zone = ImageZone::Partial(Point::new(0, 0), Point::new(60, 50));
+        assert!(analyzer.match_template(&template, zone).unwrap().error > 50.0);
+        let tiny = ImageZone::Partial(Point::new(0, 0), Point::new(20, 20));
+        assert_eq!(analyzer.match_template(&template, tiny), None);
+    }
+}
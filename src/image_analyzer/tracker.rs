@@ -0,0 +1,417 @@
+use std::time::Duration;
+
+use crate::data::{better_call_zone::Zone, point::Point};
+
+use super::{blob::Blob, kalman::KalmanFilter};
+
+/// How detections of a frame are paired with the tracks of the previous one
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Association {
+    /// Pairs zones overlapping by at least `min_iou` (intersection over union)
+    Iou { min_iou: f64 },
+    /// Pairs centroids at most `max_distance` pixels apart
+    Centroid { max_distance: f64 },
+}
+
+/// An object followed across frames
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track {
+    pub id: u64,
+    pub zone: Zone,
+    pub centroid: Point,
+    /// Frames since the track appeared, the first one included
+    pub age: u32,
+    /// Consecutive frames without a matching detection
+    pub lost: u32,
+    /// Pixels per second along x and y, zero until seen twice
+    pub velocity: (f64, f64),
+    last_seen: Duration,
+    filter: Option<KalmanFilter>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackEvent {
+    Appeared(u64),
+    Disappeared(u64),
+}
+
+/// Gives stable IDs to the zones or blobs detected frame after frame
+#[derive(Debug, Clone)]
+pub struct Tracker {
+    association: Association,
+    /// Tracks lost for more frames are dropped
+    max_lost: u32,
+    tracks: Vec<Track>,
+    next_id: u64,
+    /// Process and measurement noise of the Kalman filter of each track
+    filter: Option<(f64, f64)>,
+}
+
+/// Cost of pairs that must not be matched
+const FORBIDDEN: f64 = 1e9;
+
+fn iou(a: &Zone, b: &Zone) -> f64 {
+    let area = |zone: &Zone| {
+        (zone.end.x.saturating_sub(zone.start.x) as f64)
+            * (zone.end.y.saturating_sub(zone.start.y) as f64)
+    };
+    let width = a
+        .end
+        .x
+        .min(b.end.x)
+        .saturating_sub(a.start.x.max(b.start.x)) as f64;
+    let height = a
+        .end
+        .y
+        .min(b.end.y)
+        .saturating_sub(a.start.y.max(b.start.y)) as f64;
+    let intersection = width * height;
+    let union = area(a) + area(b) - intersection;
+    if union > 0.0 {
+        intersection / union
+    } else {
+        0.0
+    }
+}
+
+fn center(zone: &Zone) -> Point {
+    Point::new(
+        (zone.start.x + zone.end.x) / 2,
+        (zone.start.y + zone.end.y) / 2,
+    )
+}
+
+/// Minimum cost assignment of rows to columns (Hungarian algorithm, O(n²m)). Every
+/// row gets a column when there are at least as many columns as rows.
+pub fn hungarian(cost: &[Vec<f64>]) -> Vec<Option<usize>> {
+    let rows = cost.len();
+    let columns = cost.first().map_or(0, Vec::len);
+    if rows > columns {
+        let transposed: Vec<Vec<f64>> = (0..columns)
+            .map(|j| (0..rows).map(|i| cost[i][j]).collect())
+            .collect();
+        let mut assignment = vec![None; rows];
+        for (j, row) in hungarian(&transposed).into_iter().enumerate() {
+            if let Some(i) = row {
+                assignment[i] = Some(j);
+            }
+        }
+        return assignment;
+    }
+
+    // Potentials and matching are 1-indexed, column 0 being a virtual start
+    let (mut u, mut v) = (vec![0.0; rows + 1], vec![0.0; columns + 1]);
+    let mut owner = vec![0; columns + 1];
+    let mut way = vec![0; columns + 1];
+    for i in 1..=rows {
+        owner[0] = i;
+        let mut j0 = 0;
+        let mut min = vec![f64::INFINITY; columns + 1];
+        let mut used = vec![false; columns + 1];
+        loop {
+            used[j0] = true;
+            let i0 = owner[j0];
+            let (mut delta, mut j1) = (f64::INFINITY, 0);
+            for j in 1..=columns {
+                if used[j] {
+                    continue;
+                }
+                let reduced = cost[i0 - 1][j - 1] - u[i0] - v[j];
+                if reduced < min[j] {
+                    min[j] = reduced;
+                    way[j] = j0;
+                }
+                if min[j] < delta {
+                    delta = min[j];
+                    j1 = j;
+                }
+            }
+            for j in 0..=columns {
+                if used[j] {
+                    u[owner[j]] += delta;
+                    v[j] -= delta;
+                } else {
+                    min[j] -= delta;
+                }
+            }
+            j0 = j1;
+            if owner[j0] == 0 {
+                break;
+            }
+        }
+        while j0 != 0 {
+            let j1 = way[j0];
+            owner[j0] = owner[j1];
+            j0 = j1;
+        }
+    }
+    let mut assignment = vec![None; rows];
+    for j in 1..=columns {
+        if owner[j] != 0 {
+            assignment[owner[j] - 1] = Some(j - 1);
+        }
+    }
+    assignment
+}
+
+#[allow(dead_code)]
+impl Track {
+    /// Kalman filtered centroid, the detected one without a filter
+    pub fn smoothed(&self) -> Point {
+        self.filter
+            .as_ref()
+            .map_or(self.centroid.clone(), |filter| {
+                filter.predict(Duration::ZERO)
+            })
+    }
+
+    /// Expected centroid `ahead` of the last frame the track was seen in
+    pub fn predict(&self, ahead: Duration) -> Point {
+        if let Some(filter) = &self.filter {
+            return filter.predict(ahead);
+        }
+        let dt = ahead.as_secs_f64();
+        let at = |position: u32, velocity: f64| {
+            (position as f64 + velocity * dt).round().max(0.0) as u32
+        };
+        Point::new(
+            at(self.centroid.x, self.velocity.0),
+            at(self.centroid.y, self.velocity.1),
+        )
+    }
+}
+
+#[allow(dead_code)]
+impl Tracker {
+    pub fn new(association: Association, max_lost: u32) -> Self {
+        Self {
+            association,
+            max_lost,
+            tracks: Vec::new(),
+            next_id: 0,
+            filter: None,
+        }
+    }
+
+    /// Smooths the centroid and velocity of new tracks with a constant-velocity
+    /// Kalman filter, see `KalmanFilter::new` for the noise units
+    pub fn with_filter(mut self, process_noise: f64, measurement_noise: f64) -> Self {
+        self.filter = Some((process_noise, measurement_noise));
+        self
+    }
+
+    /// Tracks alive after the last update, lost ones included
+    pub fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Track> {
+        self.tracks.iter().find(|track| track.id == id)
+    }
+
+    /// Tracks zones detected at `timestamp`, centroids being their centers
+    pub fn update(&mut self, zones: &[Zone], timestamp: Duration) -> Vec<TrackEvent> {
+        let detections = zones
+            .iter()
+            .map(|zone| (zone.clone(), center(zone)))
+            .collect();
+        self.associate(detections, timestamp)
+    }
+
+    pub fn update_blobs(&mut self, blobs: &[Blob], timestamp: Duration) -> Vec<TrackEvent> {
+        let detections = blobs
+            .iter()
+            .map(|blob| (blob.zone.clone(), blob.centroid.clone()))
+            .collect();
+        self.associate(detections, timestamp)
+    }
+
+    fn cost(&self, track: &Track, zone: &Zone, centroid: &Point) -> f64 {
+        match self.association {
+            Association::Iou { min_iou } => {
+                let overlap = iou(&track.zone, zone);
+                if overlap >= min_iou && overlap > 0.0 {
+                    1.0 - overlap
+                } else {
+                    FORBIDDEN
+                }
+            }
+            Association::Centroid { max_distance } => {
+                let distance = track.centroid.distance(centroid);
+                if distance <= max_distance {
+                    distance
+                } else {
+                    FORBIDDEN
+                }
+            }
+        }
+    }
+
+    fn associate(
+        &mut self,
+        detections: Vec<(Zone, Point)>,
+        timestamp: Duration,
+    ) -> Vec<TrackEvent> {
+        let cost: Vec<Vec<f64>> = self
+            .tracks
+            .iter()
+            .map(|track| {
+                detections
+                    .iter()
+                    .map(|(zone, centroid)| self.cost(track, zone, centroid))
+                    .collect()
+            })
+            .collect();
+        let assignment = if detections.is_empty() {
+            vec![None; self.tracks.len()]
+        } else {
+            hungarian(&cost)
+        };
+
+        let mut matched = vec![false; detections.len()];
+        for (index, track) in self.tracks.iter_mut().enumerate() {
+            track.age += 1;
+            let detection = assignment[index].filter(|&j| cost[index][j] < FORBIDDEN);
+            let Some(j) = detection else {
+                track.lost += 1;
+                continue;
+            };
+            matched[j] = true;
+            let (zone, centroid) = &detections[j];
+            let elapsed = timestamp.saturating_sub(track.last_seen).as_secs_f64();
+            if let Some(filter) = &mut track.filter {
+                filter.update(centroid, timestamp);
+                track.velocity = filter.velocity();
+            } else if elapsed > 0.0 {
+                track.velocity = (
+                    (centroid.x as f64 - track.centroid.x as f64) / elapsed,
+                    (centroid.y as f64 - track.centroid.y as f64) / elapsed,
+                );
+            }
+            track.zone = zone.clone();
+            track.centroid = centroid.clone();
+            track.lost = 0;
+            track.last_seen = timestamp;
+        }
+
+        let mut events = Vec::new();
+        let max_lost = self.max_lost;
+        self.tracks.retain(|track| {
+            let keep = track.lost <= max_lost;
+            if !keep {
+                events.push(TrackEvent::Disappeared(track.id));
+            }
+            keep
+        });
+        for ((zone, centroid), _) in detections
+            .into_iter()
+            .zip(matched)
+            .filter(|(_, matched)| !matched)
+        {
+            let id = self.next_id;
+            self.next_id += 1;
+            events.push(TrackEvent::Appeared(id));
+            self.tracks.push(Track {
+                id,
+                zone,
+                age: 1,
+                lost: 0,
+                velocity: (0.0, 0.0),
+                last_seen: timestamp,
+                filter: self.filter.map(|(process_noise, measurement_noise)| {
+                    KalmanFilter::new(&centroid, timestamp, process_noise, measurement_noise)
+                }),
+                centroid,
+            });
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x: u32, y: u32) -> Zone {
+        Zone::new(Point::new(x, y), Point::new(x + 10, y + 10))
+    }
+
+    #[test]
+    fn test_hungarian_beats_greedy() {
+        // Greedy would take the 1.0 and be left with the 10.0
+        let cost = vec![vec![1.0, 2.0], vec![2.0, 10.0]];
+        assert_eq!(hungarian(&cost), vec![Some(1), Some(0)]);
+        let wide = vec![vec![5.0, 1.0, 3.0]];
+        assert_eq!(hungarian(&wide), vec![Some(1)]);
+        let tall = vec![vec![5.0], vec![1.0], vec![3.0]];
+        assert_eq!(hungarian(&tall), vec![None, Some(0), None]);
+    }
+
+    #[test]
+    fn test_ids_survive_reordering_and_loss() {
+        let mut tracker = Tracker::new(Association::Centroid { max_distance: 15.0 }, 1);
+        let second = Duration::from_secs(1);
+        let events = tracker.update(&[square(0, 0), square(100, 0)], Duration::ZERO);
+        assert_eq!(
+            events,
+            vec![TrackEvent::Appeared(0), TrackEvent::Appeared(1)]
+        );
+
+        // Same objects listed in the other order, both moved
+        let events = tracker.update(&[square(108, 0), square(5, 2)], second);
+        assert!(events.is_empty());
+        let first = tracker.get(0).unwrap();
+        assert_eq!((first.centroid.clone(), first.age), (Point::new(10, 7), 2));
+        assert_eq!(first.velocity, (5.0, 2.0));
+        assert_eq!(tracker.get(1).unwrap().velocity, (8.0, 0.0));
+
+        // Track 1 is missed once, then too often; a far zone is a new object
+        let events = tracker.update(&[square(10, 4)], second * 2);
+        assert!(events.is_empty());
+        assert_eq!(tracker.get(1).unwrap().lost, 1);
+        let events = tracker.update(&[square(15, 6), square(200, 200)], second * 3);
+        assert_eq!(
+            events,
+            vec![TrackEvent::Disappeared(1), TrackEvent::Appeared(2)]
+        );
+        assert_eq!(tracker.tracks().len(), 2);
+
+        let mut tracker = Tracker::new(Association::Iou { min_iou: 0.3 }, 0);
+        tracker.update(&[square(0, 0)], Duration::ZERO);
+        // 5x10 overlap over a 150 union: 0.33
+        assert!(tracker.update(&[square(5, 0)], second).is_empty());
+        assert_eq!(
+            tracker.update(&[square(12, 0)], second * 2),
+            vec![TrackEvent::Disappeared(0), TrackEvent::Appeared(1)]
+        );
+    }
+
+    #[test]
+    fn test_filtered_tracks_lead_moving_targets() {
+        let mut plain = Tracker::new(Association::Centroid { max_distance: 20.0 }, 0);
+        let mut filtered = plain.clone().with_filter(100.0, 4.0);
+        // 40 px/s to the right with a pixel of jitter up and down
+        for i in 0..20u32 {
+            let zone = square(4 * i, 50 + i % 2);
+            let timestamp = Duration::from_millis(100 * i as u64);
+            plain.update(std::slice::from_ref(&zone), timestamp);
+            filtered.update(&[zone], timestamp);
+        }
+        let ahead = Duration::from_millis(500);
+        // Last centroid (81, 56) plus 20 pixels; the raw velocity follows the jitter
+        assert_eq!(plain.get(0).unwrap().predict(ahead), Point::new(101, 61));
+        let track = filtered.get(0).unwrap();
+        assert!(
+            (track.velocity.0 - 40.0).abs() < 2.0,
+            "{:?}",
+            track.velocity
+        );
+        assert!(track.velocity.1.abs() < 2.0, "{:?}", track.velocity);
+        let predicted = track.predict(ahead);
+        assert!(
+            predicted.distance(&Point::new(101, 55)) <= 1.5,
+            "{predicted:?}"
+        );
+    }
+}
@@ -1,7 +1,21 @@
 use image::{ImageBuffer, Rgba};
-use image_analyzer::{pixel::PixelVec, ImageZone};
-use utils::benchmark::Benchmark;
+use image_analyzer::{
+    cache::DetectionCache,
+    color::{hsv::Hsv, rgb::Rgb},
+    detection::ColorDetection,
+    lut::CompiledDetection,
+    pixel::PixelVec,
+    pyramid::ImagePyramid,
+    simd::RgbaMatcher,
+    ImageZone,
+};
+use data::point::Point;
+use utils::{
+    benchmark::Benchmark,
+    scene::{Orientation, SceneBuilder},
+};
 
+mod config;
 mod data;
 mod image_analyzer;
 mod utils;
@@ -10,7 +24,36 @@ const BENCH_ITER: u32 = 100;
 const WIDTH: u32 = 800;
 const HEIGHT: u32 = 600;
 fn main() {
-    let image: ImageBuffer<Rgba<u8>, Vec<u8>> = image::ImageBuffer::new(WIDTH, HEIGHT);
+    let red = Rgb::from([255, 0, 0]);
+    let palette = [
+        red,
+        Rgb::from([0, 200, 0]),
+        Rgb::from([40, 90, 220]),
+        Rgb::from([250, 210, 0]),
+    ];
+    let scene = SceneBuilder::new(WIDTH, HEIGHT)
+        .gradient(
+            Rgb::from([20, 24, 36]),
+            Rgb::from([70, 80, 110]),
+            Orientation::Vertical,
+        )
+        .noise(6)
+        .random_shapes(60, &palette)
+        .text(
+            "hud",
+            Point::new(20, 20),
+            "HP 100 MP 42",
+            3,
+            Rgb::from([255, 255, 255]),
+        )
+        .render();
+    println!(
+        "Scene: {} shapes, {} red pixels",
+        scene.objects.len(),
+        scene.pixels_of(&red)
+    );
+    println!("------------------------------------------");
+    let image: ImageBuffer<Rgba<u8>, Vec<u8>> = scene.image;
 
     let mut benchmark = Benchmark::new(BENCH_ITER, "bench_par_detect_v1", true);
     benchmark.run(|i| {
@@ -18,8 +61,8 @@ fn main() {
         if i == BENCH_ITER - 1 {
             println!(
                 "Size: {}, Total: {}",
-                px_vec.pixels.len(),
-                px_vec.points_count
+                px_vec.pixels().len(),
+                px_vec.points_count()
             );
         }
     });
@@ -31,8 +74,8 @@ fn main() {
         if i == BENCH_ITER - 1 {
             println!(
                 "Size: {}, Total: {}",
-                px_vec.pixels.len(),
-                px_vec.points_count
+                px_vec.pixels().len(),
+                px_vec.points_count()
             );
         }
     });
@@ -44,12 +87,110 @@ fn main() {
         if i == BENCH_ITER - 1 {
             println!(
                 "Size: {}, Total: {}",
-                px_vec.pixels.len(),
-                px_vec.points_count
+                px_vec.pixels().len(),
+                px_vec.points_count()
+            );
+        }
+    });
+    println!("------------------------------------------");
+    let detection = ColorDetection::rgb(Rgb::from([255, 0, 0]), Rgb::from([10, 10, 10, 0]));
+    let mut benchmark = Benchmark::new(BENCH_ITER, "bench_pyramid_detect", true);
+    benchmark.run(|i| {
+        let px_vec = bench_pyramid_detect(image.clone(), &detection);
+        if i == BENCH_ITER - 1 {
+            println!(
+                "Size: {}, Total: {}",
+                px_vec.pixels().len(),
+                px_vec.points_count()
+            );
+        }
+    });
+    println!("------------------------------------------");
+    let reference = red;
+    let tolerance = Rgb::from([10, 10, 10, 0]);
+    let mut benchmark = Benchmark::new(BENCH_ITER, "bench_detect_v2_rgb", true);
+    benchmark.run(|i| {
+        let px_vec = bench_detect_v2_rgb(image.clone(), reference, tolerance);
+        if i == BENCH_ITER - 1 {
+            println!(
+                "Size: {}, Total: {}",
+                px_vec.pixels().len(),
+                px_vec.points_count()
+            );
+        }
+    });
+    println!("------------------------------------------");
+
+    let matcher = RgbaMatcher::new(reference, tolerance);
+    let mut benchmark = Benchmark::new(
+        BENCH_ITER,
+        &format!("bench_simd_detect({:?})", matcher.level()),
+        true,
+    );
+    benchmark.run(|i| {
+        let px_vec = bench_simd_detect(image.clone(), &matcher);
+        if i == BENCH_ITER - 1 {
+            println!(
+                "Size: {}, Total: {}",
+                px_vec.pixels().len(),
+                px_vec.points_count()
+            );
+        }
+    });
+    println!("------------------------------------------");
+
+    let hsv_detection = ColorDetection::hsv(
+        Hsv::from([0.0, 1.0, 1.0]),
+        Hsv::from([10.0, 0.1, 0.1, 0.0]),
+    );
+    let mut benchmark = Benchmark::new(BENCH_ITER, "bench_detect_hsv", true);
+    benchmark.run(|i| {
+        let px_vec = bench_detect(image.clone(), &hsv_detection);
+        if i == BENCH_ITER - 1 {
+            println!(
+                "Size: {}, Total: {}",
+                px_vec.pixels().len(),
+                px_vec.points_count()
+            );
+        }
+    });
+    println!("------------------------------------------");
+
+    let mut compiled = None;
+    let mut benchmark = Benchmark::new(1, "bench_compile_hsv", true);
+    benchmark.run(|_| compiled = Some(hsv_detection.compile()));
+    let compiled = compiled.unwrap();
+    let mut benchmark = Benchmark::new(BENCH_ITER, "bench_detect_compiled_hsv", true);
+    benchmark.run(|i| {
+        let px_vec = bench_detect_compiled(image.clone(), &compiled);
+        if i == BENCH_ITER - 1 {
+            println!(
+                "Size: {}, Total: {}",
+                px_vec.pixels().len(),
+                px_vec.points_count()
             );
         }
     });
     println!("------------------------------------------");
+
+    let analyzer = image_analyzer::ImageAnalyzer::new(image.clone());
+    let mut cache = DetectionCache::new();
+    let mut benchmark = Benchmark::new(BENCH_ITER, "bench_cached_detect", false);
+    benchmark.run(|i| {
+        let px_vec = cache.detect(&analyzer, ImageZone::Full, "red", &detection);
+        if i == BENCH_ITER - 1 {
+            println!(
+                "Size: {}, Total: {}",
+                px_vec.pixels().len(),
+                px_vec.points_count()
+            );
+        }
+    });
+    let stats = cache.stats();
+    benchmark.set_counter("Cache hits", stats.hits);
+    benchmark.set_counter("Cache misses", stats.misses);
+    benchmark.print();
+    println!("------------------------------------------");
     println!("Done image size: {}x{}", WIDTH, HEIGHT);
 }
 
@@ -72,8 +213,53 @@ fn bench_detect_v2(image: ImageBuffer<Rgba<u8>, Vec<u8>>) -> PixelVec {
     });
     points
 }
+fn bench_detect_v2_rgb(
+    image: ImageBuffer<Rgba<u8>, Vec<u8>>,
+    reference: Rgb,
+    tolerance: Rgb,
+) -> PixelVec {
+    let mut points = PixelVec::new();
+    let analyzer = image_analyzer::ImageAnalyzer::new(image);
+    analyzer.pixel_detectv2(ImageZone::Full, |color, point| {
+        if reference.compare(&color.get_rgb(), tolerance) {
+            points.push((color, point));
+        }
+        None
+    });
+    points
+}
+
+fn bench_detect(image: ImageBuffer<Rgba<u8>, Vec<u8>>, detection: &ColorDetection) -> PixelVec {
+    let analyzer = image_analyzer::ImageAnalyzer::new(image);
+    analyzer.detect(ImageZone::Full, detection)
+}
+
+fn bench_detect_compiled(
+    image: ImageBuffer<Rgba<u8>, Vec<u8>>,
+    detection: &CompiledDetection,
+) -> PixelVec {
+    let analyzer = image_analyzer::ImageAnalyzer::new(image);
+    analyzer.detect_compiled(ImageZone::Full, detection)
+}
+
+fn bench_simd_detect(image: ImageBuffer<Rgba<u8>, Vec<u8>>, matcher: &RgbaMatcher) -> PixelVec {
+    let analyzer = image_analyzer::ImageAnalyzer::new(image);
+    analyzer.simd_detect_pixels(ImageZone::Full, std::slice::from_ref(matcher))
+}
+
 fn bench_par_detect_v1(image: ImageBuffer<Rgba<u8>, Vec<u8>>) -> PixelVec {
     let analyzer = image_analyzer::ImageAnalyzer::new(image);
     let res = analyzer.par_pixel_detectv1();
     res
 }
+
+fn bench_pyramid_detect(
+    image: ImageBuffer<Rgba<u8>, Vec<u8>>,
+    detection: &ColorDetection,
+) -> PixelVec {
+    let analyzer = image_analyzer::ImageAnalyzer::new(image);
+    let pyramid = ImagePyramid::new(&analyzer.image, 3);
+    analyzer
+        .pyramid_detect(&pyramid, ImageZone::Full, detection)
+        .pixels
+}
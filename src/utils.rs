@@ -1 +1,3 @@
 pub mod benchmark;
+pub mod golden;
+pub mod scene;
@@ -14,6 +14,7 @@ pub struct Benchmark {
     results: Vec<std::time::Duration>,
     average: Option<std::time::Duration>,
     print_result: bool,
+    counters: Vec<(String, u64)>,
 }
 
 impl Benchmark {
@@ -24,6 +25,15 @@ impl Benchmark {
             results: Vec::new(),
             average: None,
             print_result: print,
+            counters: Vec::new(),
+        }
+    }
+
+    /// Records a named counter (cache hits, matches...) shown by `print`
+    pub fn set_counter(&mut self, name: &str, value: u64) {
+        match self.counters.iter_mut().find(|(n, _)| n == name) {
+            Some(counter) => counter.1 = value,
+            None => self.counters.push((name.to_string(), value)),
         }
     }
 
@@ -55,6 +65,9 @@ impl Benchmark {
         println!("  Fastest: {:?}", self.results.iter().min().unwrap());
         println!("  Iterations: {}", self.iterations);
         println!("  Average: {:?}", self.average);
+        for (name, value) in &self.counters {
+            println!("  {}: {}", name, value);
+        }
     }
 }
 
@@ -77,6 +90,20 @@ mod tests {
             std::thread::sleep(std::time::Duration::from_secs(1));
         });
     }
+    #[test]
+    fn test_benchmark_counters() {
+        let mut benchmark = Benchmark::new(2, "test", false);
+        benchmark.run(|_| {});
+        benchmark.set_counter("hits", 1);
+        benchmark.set_counter("misses", 1);
+        benchmark.set_counter("hits", 3);
+        assert_eq!(
+            benchmark.counters,
+            vec![("hits".to_string(), 3), ("misses".to_string(), 1)]
+        );
+        benchmark.print();
+    }
+
     #[test]
     fn test_benchmark_borrow() {
         let mut benchmark = Benchmark::new(10, "test", true);
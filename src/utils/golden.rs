@@ -0,0 +1,309 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::detectors::DetectorsConfig,
+    data::point::Point,
+    image_analyzer::{detector::DetectorSet, ImageAnalyzer},
+};
+
+/// Each directory below holds `image.png`, `detectors.json` and `expected.json`
+#[allow(dead_code)]
+pub const FIXTURES: &str = "fixtures/golden";
+/// Points are listed in a generated `expected.json` up to this count, counted above
+const LISTED_POINTS: usize = 64;
+
+fn default_tolerance() -> f64 {
+    1.0
+}
+
+/// Expected results of a fixture, only the fields present are checked
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Expected {
+    /// Maximum distance between an expected and an actual coordinate
+    #[serde(default = "default_tolerance")]
+    pub tolerance: f64,
+    pub detectors: BTreeMap<String, ExpectedResult>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExpectedResult {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub point_count: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub points: Option<Vec<Point>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zones: Option<Vec<ExpectedZone>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub centroids: Option<Vec<Point>>,
+}
+
+/// Zone corners, end excluded
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExpectedZone {
+    pub start: Point,
+    pub end: Point,
+}
+
+#[allow(dead_code)]
+pub struct GoldenCase {
+    pub name: String,
+    directory: PathBuf,
+    analyzer: ImageAnalyzer,
+    detectors: DetectorSet,
+}
+
+#[allow(dead_code)]
+impl GoldenCase {
+    pub fn load<P: AsRef<Path>>(directory: P) -> Result<Self, String> {
+        let directory = directory.as_ref().to_path_buf();
+        let name = directory
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let image_path = directory.join("image.png");
+        let image = image::open(&image_path)
+            .map_err(|error| format!("Cannot read {}: {}", image_path.display(), error))?
+            .to_rgba8();
+        let detectors =
+            DetectorsConfig::load(directory.join("detectors.json")).map_err(|errors| {
+                let errors: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+                format!("Invalid detectors of {}: {}", name, errors.join(", "))
+            })?;
+        Ok(Self {
+            name,
+            directory,
+            analyzer: ImageAnalyzer::new(image),
+            detectors,
+        })
+    }
+
+    pub fn expected(&self) -> Result<Expected, String> {
+        let path = self.directory.join("expected.json");
+        let text = fs::read_to_string(&path)
+            .map_err(|error| format!("Cannot read {}: {}", path.display(), error))?;
+        serde_json::from_str(&text)
+            .map_err(|error| format!("Invalid {}: {}", path.display(), error))
+    }
+
+    /// Runs the detectors, listing what a detector produces: blob centroids when it
+    /// extracts blobs, zones when it merges them
+    pub fn actual(&self) -> Expected {
+        let mut detectors = BTreeMap::new();
+        for (detector, result) in self
+            .detectors
+            .iter()
+            .zip(self.detectors.run(&self.analyzer))
+        {
+            let mut points = result.pixels.points();
+            points.sort_by_key(|point| (point.y, point.x));
+            let expected = ExpectedResult {
+                point_count: Some(points.len()),
+                points: (points.len() <= LISTED_POINTS).then_some(points),
+                zones: detector.post.merge_zones.then(|| {
+                    result
+                        .zones
+                        .iter()
+                        .map(|zone| ExpectedZone {
+                            start: zone.start.clone(),
+                            end: zone.end.clone(),
+                        })
+                        .collect()
+                }),
+                centroids: detector.post.blobs.as_ref().map(|_| {
+                    result
+                        .blobs
+                        .iter()
+                        .map(|blob| blob.centroid.clone())
+                        .collect()
+                }),
+            };
+            detectors.insert(result.name, expected);
+        }
+        Expected {
+            tolerance: default_tolerance(),
+            detectors,
+        }
+    }
+
+    /// Differences between `expected.json` and the detectors, empty when they agree
+    pub fn check(&self) -> Result<Vec<String>, String> {
+        Ok(compare(&self.expected()?, &self.actual()))
+    }
+
+    /// Writes the current results as `expected.json`, keeping the tolerance
+    pub fn update(&self) -> Result<(), String> {
+        let mut actual = self.actual();
+        if let Ok(expected) = self.expected() {
+            actual.tolerance = expected.tolerance;
+        }
+        let path = self.directory.join("expected.json");
+        let json = serde_json::to_string_pretty(&actual).unwrap();
+        fs::write(&path, json + "\n")
+            .map_err(|error| format!("Cannot write {}: {}", path.display(), error))
+    }
+}
+
+/// Every fixture directory under `root`, by name
+#[allow(dead_code)]
+pub fn cases<P: AsRef<Path>>(root: P) -> Result<Vec<GoldenCase>, String> {
+    let root = root.as_ref();
+    let mut directories: Vec<PathBuf> = fs::read_dir(root)
+        .map_err(|error| format!("Cannot read {}: {}", root.display(), error))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_dir())
+        .collect();
+    directories.sort();
+    directories.iter().map(GoldenCase::load).collect()
+}
+
+/// Human readable differences, `detector: what` per line
+#[allow(dead_code)]
+pub fn compare(expected: &Expected, actual: &Expected) -> Vec<String> {
+    let tolerance = expected.tolerance;
+    let mut diffs = Vec::new();
+    for name in actual.detectors.keys() {
+        if !expected.detectors.contains_key(name) {
+            diffs.push(format!("{}: no expected results", name));
+        }
+    }
+    for (name, expected) in &expected.detectors {
+        let Some(actual) = actual.detectors.get(name) else {
+            diffs.push(format!("{}: no such detector", name));
+            continue;
+        };
+        let mut push = |diff: String| diffs.push(format!("{}: {}", name, diff));
+        if let (Some(expected), Some(actual)) = (expected.point_count, actual.point_count) {
+            if expected != actual {
+                push(format!("{} points instead of {}", actual, expected));
+            }
+        }
+        if let Some(expected) = &expected.points {
+            let actual = actual.points.clone().unwrap_or_default();
+            compare_points("point", expected, &actual, tolerance, &mut push);
+        }
+        if let Some(expected) = &expected.centroids {
+            let actual = actual.centroids.clone().unwrap_or_default();
+            compare_points("centroid", expected, &actual, tolerance, &mut push);
+        }
+        if let Some(expected) = &expected.zones {
+            let actual = actual.zones.clone().unwrap_or_default();
+            let mut unused: Vec<&ExpectedZone> = actual.iter().collect();
+            for zone in expected {
+                let found = unused.iter().position(|other| {
+                    zone.start.distance(&other.start) <= tolerance
+                        && zone.end.distance(&other.end) <= tolerance
+                });
+                match found {
+                    Some(index) => {
+                        unused.remove(index);
+                    }
+                    None => push(format!("missing zone {}", format_zone(zone))),
+                }
+            }
+            for zone in unused {
+                push(format!("unexpected zone {}", format_zone(zone)));
+            }
+        }
+    }
+    diffs
+}
+
+/// Pairs each expected point with the closest unused actual point within `tolerance`
+fn compare_points<F>(what: &str, expected: &[Point], actual: &[Point], tolerance: f64, push: &mut F)
+where
+    F: FnMut(String),
+{
+    let mut unused: Vec<&Point> = actual.iter().collect();
+    for point in expected {
+        let closest = unused
+            .iter()
+            .enumerate()
+            .map(|(index, other)| (index, point.distance(other)))
+            .filter(|(_, distance)| *distance <= tolerance)
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        match closest {
+            Some((index, _)) => {
+                unused.remove(index);
+            }
+            None => push(format!("missing {} ({}, {})", what, point.x, point.y)),
+        }
+    }
+    for point in unused {
+        push(format!("unexpected {} ({}, {})", what, point.x, point.y));
+    }
+}
+
+fn format_zone(zone: &ExpectedZone) -> String {
+    format!(
+        "({}, {})-({}, {})",
+        zone.start.x, zone.start.y, zone.end.x, zone.end.y
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `UPDATE_GOLDEN=1 cargo test golden` rewrites the expected results
+    #[test]
+    fn test_golden_fixtures() {
+        let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+        let mut failures = Vec::new();
+        for case in cases(FIXTURES).unwrap() {
+            if update {
+                case.update().unwrap();
+            }
+            for diff in case.check().unwrap() {
+                failures.push(format!("{}/{}", case.name, diff));
+            }
+        }
+        assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+    }
+
+    #[test]
+    fn test_compare_tolerance() {
+        let expected = Expected {
+            tolerance: 1.5,
+            detectors: BTreeMap::from([(
+                "a".to_string(),
+                ExpectedResult {
+                    point_count: Some(2),
+                    centroids: Some(vec![Point::new(10, 10), Point::new(20, 20)]),
+                    zones: Some(vec![ExpectedZone {
+                        start: Point::new(0, 0),
+                        end: Point::new(5, 5),
+                    }]),
+                    ..Default::default()
+                },
+            )]),
+        };
+        let mut actual = expected.clone();
+        let result = actual.detectors.get_mut("a").unwrap();
+        result.centroids = Some(vec![Point::new(21, 21), Point::new(11, 9)]);
+        result.zones.as_mut().unwrap()[0].end = Point::new(6, 5);
+        assert!(compare(&expected, &actual).is_empty());
+
+        let result = actual.detectors.get_mut("a").unwrap();
+        result.point_count = Some(3);
+        result.centroids = Some(vec![Point::new(10, 10), Point::new(23, 20)]);
+        actual
+            .detectors
+            .insert("b".to_string(), ExpectedResult::default());
+        assert_eq!(
+            compare(&expected, &actual),
+            vec![
+                "b: no expected results",
+                "a: 3 points instead of 2",
+                "a: missing centroid (20, 20)",
+                "a: unexpected centroid (23, 20)",
+            ]
+        );
+    }
+}
@@ -0,0 +1,388 @@
+use image::{ImageBuffer, Rgba};
+
+use crate::{
+    data::{better_call_zone::Zone, point::Point},
+    image_analyzer::{
+        color::rgb::Rgb,
+        debug_image::{glyph, GLYPH_WIDTH},
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Orientation {
+    Horizontal,
+    Vertical,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShapeKind {
+    Rect,
+    Circle,
+    /// Characters drawn with the 3x5 debug font
+    Text(String),
+}
+
+/// Ground truth of one drawn shape
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneObject {
+    pub label: String,
+    pub kind: ShapeKind,
+    pub color: Rgb,
+    /// Bounding zone of the drawn pixels, end excluded
+    pub zone: Zone,
+    /// Pixels still visible once every later shape is drawn over it
+    pub pixel_count: usize,
+}
+
+pub struct Scene {
+    pub image: ImageBuffer<Rgba<u8>, Vec<u8>>,
+    /// Shapes in drawing order
+    pub objects: Vec<SceneObject>,
+}
+
+#[allow(dead_code)]
+impl Scene {
+    pub fn object(&self, label: &str) -> Option<&SceneObject> {
+        self.objects.iter().find(|object| object.label == label)
+    }
+
+    /// Visible pixels drawn with exactly `color` by shapes
+    pub fn pixels_of(&self, color: &Rgb) -> usize {
+        self.objects
+            .iter()
+            .filter(|object| object.color == *color)
+            .map(|object| object.pixel_count)
+            .sum()
+    }
+}
+
+enum Item {
+    Rect(Zone),
+    Circle(Point, u32),
+    Text(Point, String, u32),
+}
+
+/// Deterministic xorshift generator, scenes only depend on their seed
+struct SceneRng(u64);
+
+impl SceneRng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Uniform in `min..max`
+    fn range(&mut self, min: u32, max: u32) -> u32 {
+        min + (self.next() % (max - min) as u64) as u32
+    }
+}
+
+/// Renders scenes with known content. Background gradient and noise never touch
+/// shapes, so shape colors stay exact and `Scene::objects` is the ground truth.
+pub struct SceneBuilder {
+    width: u32,
+    height: u32,
+    seed: u64,
+    background: (Rgb, Rgb, Orientation),
+    noise: u8,
+    items: Vec<(String, Rgb, Item)>,
+}
+
+#[allow(dead_code)]
+impl SceneBuilder {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            seed: 0x9e37_79b9_7f4a_7c15,
+            background: (
+                Rgb::from([0, 0, 0]),
+                Rgb::from([0, 0, 0]),
+                Orientation::Horizontal,
+            ),
+            noise: 0,
+            items: Vec::new(),
+        }
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        // xorshift never leaves zero
+        self.seed = seed.max(1);
+        self
+    }
+
+    pub fn background(self, color: Rgb) -> Self {
+        self.gradient(color, color, Orientation::Horizontal)
+    }
+
+    /// Background going from `from` on the left or top to `to` on the right or bottom
+    pub fn gradient(mut self, from: Rgb, to: Rgb, orientation: Orientation) -> Self {
+        self.background = (from, to, orientation);
+        self
+    }
+
+    /// Adds up to `amplitude` to or from each background channel
+    pub fn noise(mut self, amplitude: u8) -> Self {
+        self.noise = amplitude;
+        self
+    }
+
+    pub fn rect(mut self, label: &str, zone: Zone, color: Rgb) -> Self {
+        self.items
+            .push((label.to_string(), color, Item::Rect(zone)));
+        self
+    }
+
+    /// Disc of the pixels whose center is within `radius` of `center`
+    pub fn circle(mut self, label: &str, center: Point, radius: u32, color: Rgb) -> Self {
+        self.items
+            .push((label.to_string(), color, Item::Circle(center, radius)));
+        self
+    }
+
+    /// Text-like pattern, glyphs `scale` pixels per dot with one dot between them
+    pub fn text(mut self, label: &str, origin: Point, text: &str, scale: u32, color: Rgb) -> Self {
+        self.items.push((
+            label.to_string(),
+            color,
+            Item::Text(origin, text.to_string(), scale.max(1)),
+        ));
+        self
+    }
+
+    /// Adds `count` rectangles and circles labelled `shape_<n>` with colors from `palette`,
+    /// nothing when the palette is empty
+    pub fn random_shapes(mut self, count: usize, palette: &[Rgb]) -> Self {
+        if palette.is_empty() {
+            return self;
+        }
+        let mut rng = SceneRng(self.seed);
+        let max = self.width.min(self.height).clamp(2, 64);
+        for index in 0..count {
+            let color = palette[rng.range(0, palette.len() as u32) as usize];
+            let size = rng.range(max / 8 + 1, max);
+            let x = rng.range(0, self.width.saturating_sub(size).max(1));
+            let y = rng.range(0, self.height.saturating_sub(size).max(1));
+            let label = format!("shape_{}", index);
+            self = if rng.next().is_multiple_of(2) {
+                let end = Point::new((x + size).min(self.width), (y + size).min(self.height));
+                self.rect(&label, Zone::new(Point::new(x, y), end), color)
+            } else {
+                let radius = size / 2;
+                self.circle(&label, Point::new(x + radius, y + radius), radius, color)
+            };
+        }
+        self.seed = rng.next();
+        self
+    }
+
+    pub fn render(&self) -> Scene {
+        let (from, to, orientation) = self.background;
+        let mut rng = SceneRng(self.seed);
+        let noise = self.noise as i32;
+        let mut image = ImageBuffer::from_fn(self.width, self.height, |x, y| {
+            let (position, length) = match orientation {
+                Orientation::Horizontal => (x, self.width),
+                Orientation::Vertical => (y, self.height),
+            };
+            let t = position as f64 / (length.max(2) - 1) as f64;
+            let mut channel = |from: u8, to: u8| {
+                let value = from as f64 + (to as f64 - from as f64) * t;
+                let offset = if noise > 0 {
+                    (rng.next() % (2 * noise as u64 + 1)) as i32 - noise
+                } else {
+                    0
+                };
+                (value.round() as i32 + offset).clamp(0, 255) as u8
+            };
+            Rgba([
+                channel(from.r, to.r),
+                channel(from.g, to.g),
+                channel(from.b, to.b),
+                255,
+            ])
+        });
+
+        // Last shape drawn over each pixel
+        let mut owners: Vec<Option<usize>> = vec![None; (self.width * self.height) as usize];
+        for (index, (_, color, item)) in self.items.iter().enumerate() {
+            for point in self.item_points(item) {
+                owners[(point.y * self.width + point.x) as usize] = Some(index);
+                image.put_pixel(point.x, point.y, Rgba([color.r, color.g, color.b, color.a]));
+            }
+        }
+        let mut counts = vec![0; self.items.len()];
+        for index in owners.iter().flatten() {
+            counts[*index] += 1;
+        }
+
+        let objects = self
+            .items
+            .iter()
+            .zip(counts)
+            .map(|((label, color, item), pixel_count)| {
+                let points = self.item_points(item);
+                let zone = match points.first() {
+                    Some(first) => {
+                        let (mut min, mut max) = (first.clone(), first.clone());
+                        for point in &points {
+                            min = Point::new(min.x.min(point.x), min.y.min(point.y));
+                            max = Point::new(max.x.max(point.x), max.y.max(point.y));
+                        }
+                        Zone::new(min, Point::new(max.x + 1, max.y + 1))
+                    }
+                    None => Zone::new(Point::new(0, 0), Point::new(0, 0)),
+                };
+                SceneObject {
+                    label: label.clone(),
+                    kind: match item {
+                        Item::Rect(_) => ShapeKind::Rect,
+                        Item::Circle(..) => ShapeKind::Circle,
+                        Item::Text(_, text, _) => ShapeKind::Text(text.clone()),
+                    },
+                    color: *color,
+                    zone,
+                    pixel_count,
+                }
+            })
+            .collect();
+        Scene { image, objects }
+    }
+
+    /// Pixels covered by a shape, clipped to the image
+    fn item_points(&self, item: &Item) -> Vec<Point> {
+        let mut points = Vec::new();
+        let mut push = |x: u32, y: u32| {
+            if x < self.width && y < self.height {
+                points.push(Point::new(x, y));
+            }
+        };
+        match item {
+            Item::Rect(zone) => {
+                for y in zone.start.y..zone.end.y {
+                    for x in zone.start.x..zone.end.x {
+                        push(x, y);
+                    }
+                }
+            }
+            Item::Circle(center, radius) => {
+                let r = *radius as i64;
+                for dy in -r..=r {
+                    for dx in -r..=r {
+                        let (x, y) = (center.x as i64 + dx, center.y as i64 + dy);
+                        if dx * dx + dy * dy <= r * r && x >= 0 && y >= 0 {
+                            push(x as u32, y as u32);
+                        }
+                    }
+                }
+            }
+            Item::Text(origin, text, scale) => {
+                for (index, c) in text.chars().enumerate() {
+                    let left = origin.x + index as u32 * (GLYPH_WIDTH + 1) * scale;
+                    for (row, bits) in glyph(c).iter().enumerate() {
+                        for column in 0..GLYPH_WIDTH {
+                            if bits & (1 << (GLYPH_WIDTH - 1 - column)) == 0 {
+                                continue;
+                            }
+                            for dy in 0..*scale {
+                                for dx in 0..*scale {
+                                    push(
+                                        left + column * scale + dx,
+                                        origin.y + row as u32 * scale + dy,
+                                    );
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        points
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image_analyzer::{detection::ColorDetection, detector::*, ImageAnalyzer, ImageZone};
+
+    #[test]
+    fn test_ground_truth_matches_detection() {
+        let red = Rgb::from([255, 0, 0]);
+        let blue = Rgb::from([0, 0, 255]);
+        let scene = SceneBuilder::new(200, 120)
+            .gradient(
+                Rgb::from([20, 40, 60]),
+                Rgb::from([60, 80, 100]),
+                Orientation::Vertical,
+            )
+            .noise(8)
+            .rect(
+                "bar",
+                Zone::new(Point::new(10, 10), Point::new(60, 20)),
+                red,
+            )
+            .circle("coin", Point::new(150, 60), 10, blue)
+            .rect(
+                "cover",
+                Zone::new(Point::new(50, 5), Point::new(55, 25)),
+                blue,
+            )
+            .text("score", Point::new(10, 100), "HP 42", 2, red)
+            .render();
+
+        let bar = scene.object("bar").unwrap();
+        assert_eq!(bar.pixel_count, 500 - 50);
+        assert_eq!(bar.zone, Zone::new(Point::new(10, 10), Point::new(60, 20)));
+        let coin = scene.object("coin").unwrap();
+        assert_eq!(coin.kind, ShapeKind::Circle);
+        assert_eq!(
+            coin.zone,
+            Zone::new(Point::new(140, 50), Point::new(161, 71))
+        );
+
+        let analyzer = ImageAnalyzer::new(scene.image.clone());
+        for color in [red, blue] {
+            let detection = ColorDetection::rgb(color, Rgb::from([0, 0, 0, 0]));
+            let found = analyzer.detect(ImageZone::Full, &detection).points_count();
+            assert_eq!(found, scene.pixels_of(&color));
+        }
+
+        // Separate glyphs once blobs are extracted from a scaled text
+        let detector = Detector::new(
+            "score",
+            ColorDetection::rgb(red, Rgb::from([0, 0, 0, 0])),
+            vec![ImageZone::Partial(Point::new(0, 90), Point::new(200, 120))],
+            ScanStrategy::Full,
+            PostProcess {
+                blobs: Some(BlobOptions { min_size: 1 }),
+                merge_zones: false,
+            },
+        )
+        .unwrap();
+        assert_eq!(detector.run(&analyzer).blobs.len(), 4);
+    }
+
+    #[test]
+    fn test_random_shapes_are_seeded() {
+        let palette = [Rgb::from([255, 0, 0]), Rgb::from([0, 255, 0])];
+        let render = |seed| {
+            SceneBuilder::new(320, 240)
+                .seed(seed)
+                .noise(4)
+                .random_shapes(12, &palette)
+                .render()
+        };
+        let (a, b, c) = (render(7), render(7), render(8));
+        assert_eq!(a.objects.len(), 12);
+        assert!(a.image == b.image && a.objects == b.objects);
+        assert!(a.image != c.image);
+        assert!(SceneBuilder::new(32, 32)
+            .random_shapes(3, &[])
+            .render()
+            .objects
+            .is_empty());
+    }
+}